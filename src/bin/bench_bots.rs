@@ -0,0 +1,42 @@
+use snake_rust_wasm::{
+    config::GameConfig,
+    controller::{Strategy, play_round},
+    simulation::State,
+};
+
+const ROUNDS: u64 = 100;
+const MAX_TICKS: u64 = 20_000;
+
+fn main() {
+    let rounds = std::env::args()
+        .nth(1)
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(ROUNDS);
+
+    println!("strategy     avg score  max score  deaths  avg ticks");
+    for strategy in Strategy::ALL {
+        let (mut total_score, mut max_score, mut deaths, mut total_ticks) = (0, 0, 0, 0);
+        for seed in 0..rounds {
+            let sim = play_round(
+                GameConfig::default(),
+                seed,
+                &mut [strategy.controller()],
+                MAX_TICKS,
+            );
+            total_score += sim.score() as u64;
+            max_score = max_score.max(sim.score());
+            total_ticks += sim.tick();
+            if sim.state() == State::GameOver && sim.players().iter().any(|p| !p.is_alive()) {
+                deaths += 1;
+            }
+        }
+        println!(
+            "{:<12} {:>9.1}  {:>9}  {:>6}  {:>9.0}",
+            strategy.to_string(),
+            total_score as f64 / rounds as f64,
+            max_score,
+            deaths,
+            total_ticks as f64 / rounds as f64
+        );
+    }
+}
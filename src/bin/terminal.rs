@@ -0,0 +1,147 @@
+//! Plays the game in a terminal, e.g. over SSH:
+//!
+//! ```sh
+//! cargo run --release --bin terminal -- --seed 42 --players 2 --level box --theme colorblind
+//! ```
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> std::io::Result<()> {
+    terminal::run()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod terminal {
+    use std::{
+        io::{self, Write},
+        time::{Duration, Instant},
+    };
+
+    use crossterm::{
+        cursor::{Hide, Show},
+        event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+        execute,
+        terminal::{
+            Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode,
+            enable_raw_mode,
+        },
+    };
+    use snake_rust_wasm::{
+        config::GameConfig, controller::Strategy, game::Game, render::TerminalRenderer,
+        simulation::MAX_PLAYERS, theme::Theme,
+    };
+
+    const FRAME_TIME: Duration = Duration::from_millis(16);
+
+    /// Puts the terminal back the way it was, even when the game panics.
+    struct RawMode;
+
+    impl RawMode {
+        fn enter(out: &mut impl Write) -> io::Result<Self> {
+            enable_raw_mode()?;
+            execute!(out, EnterAlternateScreen, Hide, Clear(ClearType::All))?;
+            Ok(RawMode)
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+            let _ = disable_raw_mode();
+        }
+    }
+
+    fn arg(name: &str) -> Option<String> {
+        let flag = format!("--{}", name);
+        let mut args = std::env::args().skip_while(|arg| *arg != flag);
+        args.next()?;
+        args.next()
+    }
+
+    /// The name the browser gives the key in `KeyboardEvent.key`.
+    fn key_name(code: KeyCode) -> Option<String> {
+        match code {
+            KeyCode::Char(c) => Some(c.to_string()),
+            KeyCode::Up => Some("ArrowUp".to_string()),
+            KeyCode::Down => Some("ArrowDown".to_string()),
+            KeyCode::Left => Some("ArrowLeft".to_string()),
+            KeyCode::Right => Some("ArrowRight".to_string()),
+            _ => None,
+        }
+    }
+
+    /// The name the browser gives the key in `KeyboardEvent.code`, as on a US
+    /// layout, since a terminal only tells which character was typed.
+    fn key_code(code: KeyCode) -> Option<String> {
+        match code {
+            KeyCode::Char(' ') => Some("Space".to_string()),
+            KeyCode::Char('=') => Some("Equal".to_string()),
+            KeyCode::Char('-') => Some("Minus".to_string()),
+            KeyCode::Char(c) if c.is_ascii_alphabetic() => {
+                Some(format!("Key{}", c.to_ascii_uppercase()))
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() => Some(format!("Digit{}", c)),
+            KeyCode::Char(_) => None,
+            _ => key_name(code),
+        }
+    }
+
+    pub fn run() -> io::Result<()> {
+        let seed = arg("seed")
+            .and_then(|seed| seed.parse().ok())
+            .unwrap_or_else(|| {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |time| time.as_nanos() as u64)
+            });
+        let config = GameConfig::default()
+            .with_params(arg)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let renderer = TerminalRenderer::new(config.cols, config.rows, config.cell_size);
+        let mut game = Game::new(renderer.clone(), config, seed);
+        for (player, slot) in arg("bots")
+            .unwrap_or_default()
+            .split(',')
+            .take(MAX_PLAYERS)
+            .enumerate()
+        {
+            if let Ok(strategy) = slot.parse::<Strategy>() {
+                game.set_controller(player, Some(strategy.controller()));
+            }
+        }
+        if let Some(theme) = arg("theme").and_then(|name| Theme::builtin(&name)) {
+            game.set_theme(theme);
+        }
+
+        let mut stdout = io::stdout();
+        let raw_mode = RawMode::enter(&mut stdout)?;
+        let mut prev = Instant::now();
+        loop {
+            if event::poll(FRAME_TIME)?
+                && let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                let ctrl_c =
+                    key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c');
+                if ctrl_c || key.code == KeyCode::Esc {
+                    break;
+                }
+                if let Some(name) = key_name(key.code) {
+                    game.keydown(&key_code(key.code).unwrap_or_default(), &name);
+                }
+            }
+
+            let now = Instant::now();
+            game.update((now - prev).as_secs_f32());
+            prev = now;
+            game.render();
+            renderer.present(&mut stdout)?;
+        }
+        drop(raw_mode);
+
+        println!("seed = {}, score = {}", seed, game.simulation().score());
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use std::process::ExitCode;
+
+use snake_rust_wasm::replay::Replay;
+
+fn main() -> ExitCode {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("usage: verify_replay <replay-file>");
+        return ExitCode::FAILURE;
+    };
+
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match text.parse::<Replay>().and_then(|replay| replay.verify()) {
+        Ok(summary) => {
+            println!("seed  = {}", summary.seed);
+            println!("tick  = {}", summary.tick);
+            println!("state = {:?}", summary.state);
+            println!("score = {}", summary.score);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            ExitCode::FAILURE
+        }
+    }
+}
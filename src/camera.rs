@@ -0,0 +1,260 @@
+//! The camera of the `dynamic_camera` feature. It eases after a target, the
+//! snake head, once that leaves a dead zone around the centre of the view,
+//! shakes on request and zooms in and out. On a wrapping board it wraps too,
+//! and the world is drawn once per board copy that shows on the screen.
+
+use std::ops::RangeInclusive;
+
+use crate::simulation::Vec2;
+
+// Constants
+/// Share of the remaining distance to the target covered per second, as a rate of `exp`.
+const CAMERA_EASE_RATE: f32 = 4.0;
+const CAMERA_ZOOM_EASE_RATE: f32 = 8.0;
+/// Share of the view, each way, in which the target moves without the camera following it.
+const CAMERA_DEAD_ZONE_PERCENT: f32 = 0.25;
+pub const CAMERA_MIN_ZOOM: f32 = 0.5;
+pub const CAMERA_MAX_ZOOM: f32 = 4.0;
+const CAMERA_SHAKE_DURATION: f32 = 0.6;
+/// Angular frequencies of the shake along x and y, picked not to line up.
+const CAMERA_SHAKE_FREQUENCY: Vec2<f32> = Vec2 { x: 71.0, y: 53.0 };
+
+pub struct Camera {
+    /// Size of the board, which is also the size of the screen.
+    size: Vec2<f32>,
+    wrap: bool,
+    /// The point of the board at the centre of the screen.
+    pos: Vec2<f32>,
+    zoom: f32,
+    target_zoom: f32,
+    shake_amplitude: f32,
+    shake_timer: f32,
+    time: f32,
+}
+
+/// Distance to go from `from` to `to`, the short way round when `size` wraps.
+fn wrapped_delta(from: f32, to: f32, size: f32, wrap: bool) -> f32 {
+    let delta = to - from;
+    if wrap {
+        (delta + size * 0.5).rem_euclid(size) - size * 0.5
+    } else {
+        delta
+    }
+}
+
+/// The part of `delta` that sticks out of `-dead_zone..=dead_zone`.
+fn outside_dead_zone(delta: f32, dead_zone: f32) -> f32 {
+    delta - delta.clamp(-dead_zone, dead_zone)
+}
+
+/// Board copies `k` for which `start..end` moved by `k * size` overlaps `view_start..view_end`.
+fn tiles(start: f32, end: f32, view_start: f32, view_end: f32, size: f32) -> RangeInclusive<i32> {
+    let first = ((view_start - end) / size).floor() as i32 + 1;
+    let last = ((view_end - start) / size).ceil() as i32 - 1;
+    first..=last
+}
+
+impl Camera {
+    /// A camera looking at the whole board at zoom 1.
+    pub fn new(size: Vec2<f32>, wrap: bool) -> Self {
+        Self {
+            size,
+            wrap,
+            pos: Vec2 {
+                x: size.x / 2.0,
+                y: size.y / 2.0,
+            },
+            zoom: 1.0,
+            target_zoom: 1.0,
+            shake_amplitude: 0.0,
+            shake_timer: 0.0,
+            time: 0.0,
+        }
+    }
+
+    /// Looks at the whole board of a new round again, keeping the zoom.
+    pub fn reset(&mut self, size: Vec2<f32>, wrap: bool) {
+        let zoom = self.target_zoom;
+        *self = Self::new(size, wrap);
+        self.zoom = zoom;
+        self.target_zoom = zoom;
+    }
+
+    pub fn pos(&self) -> Vec2<f32> {
+        self.pos
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// The zoom the camera is easing towards.
+    pub fn target_zoom(&self) -> f32 {
+        self.target_zoom
+    }
+
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.target_zoom = zoom.clamp(CAMERA_MIN_ZOOM, CAMERA_MAX_ZOOM);
+    }
+
+    /// Shakes the view by up to `amplitude` screen pixels, fading out.
+    pub fn shake(&mut self, amplitude: f32) {
+        self.shake_amplitude = amplitude;
+        self.shake_timer = CAMERA_SHAKE_DURATION;
+    }
+
+    /// Moves towards `target`, a point of the board, or stays put without one.
+    pub fn update(&mut self, target: Option<Vec2<f32>>, dt: f32) {
+        self.time += dt;
+        self.shake_timer = (self.shake_timer - dt).max(0.0);
+        self.zoom += (self.target_zoom - self.zoom) * (1.0 - (-CAMERA_ZOOM_EASE_RATE * dt).exp());
+
+        if let Some(target) = target {
+            let ease = 1.0 - (-CAMERA_EASE_RATE * dt).exp();
+            let view = self.view_size();
+            let dx = wrapped_delta(self.pos.x, target.x, self.size.x, self.wrap);
+            let dy = wrapped_delta(self.pos.y, target.y, self.size.y, self.wrap);
+            self.pos.x += outside_dead_zone(dx, view.x * CAMERA_DEAD_ZONE_PERCENT) * ease;
+            self.pos.y += outside_dead_zone(dy, view.y * CAMERA_DEAD_ZONE_PERCENT) * ease;
+        }
+
+        if self.wrap {
+            self.pos.x = self.pos.x.rem_euclid(self.size.x);
+            self.pos.y = self.pos.y.rem_euclid(self.size.y);
+        } else {
+            // Keep the view on the board, or centred when it shows all of it.
+            let view = self.view_size();
+            let clamp = |pos: f32, half: f32, size: f32| {
+                if half * 2.0 >= size {
+                    size / 2.0
+                } else {
+                    pos.clamp(half, size - half)
+                }
+            };
+            self.pos.x = clamp(self.pos.x, view.x / 2.0, self.size.x);
+            self.pos.y = clamp(self.pos.y, view.y / 2.0, self.size.y);
+        }
+    }
+
+    /// Size of the part of the board on the screen.
+    fn view_size(&self) -> Vec2<f32> {
+        Vec2 {
+            x: self.size.x / self.zoom,
+            y: self.size.y / self.zoom,
+        }
+    }
+
+    /// Top left and bottom right corners of the part of the board on the screen.
+    pub fn view(&self) -> (Vec2<f32>, Vec2<f32>) {
+        let view = self.view_size();
+        let min = Vec2 {
+            x: self.pos.x - view.x / 2.0,
+            y: self.pos.y - view.y / 2.0,
+        };
+        (min, min + view)
+    }
+
+    fn shake_offset(&self) -> Vec2<f32> {
+        let fade = self.shake_timer / CAMERA_SHAKE_DURATION;
+        let amplitude = self.shake_amplitude * fade * fade;
+        Vec2 {
+            x: amplitude * (self.time * CAMERA_SHAKE_FREQUENCY.x).sin(),
+            y: amplitude * (self.time * CAMERA_SHAKE_FREQUENCY.y).cos(),
+        }
+    }
+
+    /// Screen position of a point of the board.
+    pub fn to_screen(&self, point: Vec2<f32>) -> Vec2<f32> {
+        let shake = self.shake_offset();
+        Vec2 {
+            x: (point.x - self.pos.x) * self.zoom + self.size.x / 2.0 + shake.x,
+            y: (point.y - self.pos.y) * self.zoom + self.size.y / 2.0 + shake.y,
+        }
+    }
+
+    /// Offsets by whole boards at which the box from `min` to `max` shows on
+    /// the screen. Only a wrapping board has copies besides the board itself.
+    pub fn tile_offsets(&self, min: Vec2<f32>, max: Vec2<f32>) -> Vec<Vec2<f32>> {
+        if !self.wrap {
+            return vec![Vec2::default()];
+        }
+        let (view_min, view_max) = self.view();
+        let cols = tiles(min.x, max.x, view_min.x, view_max.x, self.size.x);
+        let rows = tiles(min.y, max.y, view_min.y, view_max.y, self.size.y);
+        rows.flat_map(|row| {
+            cols.clone().map(move |col| Vec2 {
+                x: col as f32 * self.size.x,
+                y: row as f32 * self.size.y,
+            })
+        })
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: Vec2<f32> = Vec2 { x: 160.0, y: 90.0 };
+
+    #[test]
+    fn target_inside_dead_zone_keeps_camera_still() {
+        let mut camera = Camera::new(SIZE, true);
+        camera.update(Some(Vec2 { x: 100.0, y: 50.0 }), 1.0);
+        assert_eq!(camera.pos(), Vec2 { x: 80.0, y: 45.0 });
+    }
+
+    #[test]
+    fn camera_follows_across_the_seam() {
+        let mut camera = Camera::new(SIZE, true);
+        let mut prev = camera.pos();
+        for i in 0..400 {
+            let target = Vec2 {
+                x: (80.0 + i as f32) % SIZE.x,
+                y: 45.0,
+            };
+            camera.update(Some(target), 0.1);
+            let pos = camera.pos();
+            // No jump back over the whole board when the target wraps.
+            assert!(
+                wrapped_delta(prev.x, pos.x, SIZE.x, true).abs() < 2.0,
+                "{:?}",
+                pos
+            );
+            let behind = wrapped_delta(pos.x, target.x, SIZE.x, true);
+            assert!(
+                behind.abs() <= SIZE.x * CAMERA_DEAD_ZONE_PERCENT + 3.0,
+                "{:?}",
+                pos
+            );
+            prev = pos;
+        }
+    }
+
+    #[test]
+    fn solid_board_keeps_the_view_on_it() {
+        let mut camera = Camera::new(SIZE, false);
+        camera.update(Some(Vec2 { x: 0.0, y: 0.0 }), 1.0);
+        assert_eq!(camera.pos(), Vec2 { x: 80.0, y: 45.0 });
+
+        camera.set_zoom(2.0);
+        for _ in 0..100 {
+            camera.update(Some(Vec2 { x: 0.0, y: 0.0 }), 0.1);
+        }
+        let (min, _) = camera.view();
+        assert!(min.x.abs() < 1e-3 && min.y.abs() < 1e-3, "{:?}", min);
+    }
+
+    #[test]
+    fn tiles_cover_the_view() {
+        let mut camera = Camera::new(SIZE, true);
+        camera.update(Some(Vec2 { x: 155.0, y: 45.0 }), 10.0);
+        let (min, max) = camera.view();
+        assert!(max.x > SIZE.x);
+        let offsets = camera.tile_offsets(Vec2 { x: 0.0, y: 0.0 }, Vec2 { x: 10.0, y: 10.0 });
+        assert_eq!(offsets, vec![Vec2 { x: SIZE.x, y: 0.0 }]);
+        let offsets = camera.tile_offsets(Vec2 { x: 150.0, y: 0.0 }, Vec2 { x: 160.0, y: 10.0 });
+        assert_eq!(offsets, vec![Vec2::default()]);
+        assert!(min.x < 150.0);
+    }
+}
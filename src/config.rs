@@ -0,0 +1,439 @@
+use std::{fmt, sync::Arc};
+
+use crate::{
+    level::Level,
+    simulation::{Cell, MAX_PLAYERS, SNAKE_MIN_SIZE},
+};
+
+/// What happens when the snake leaves the board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Edges {
+    #[default]
+    Wrap,
+    Solid,
+}
+
+impl fmt::Display for Edges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Edges::Wrap => write!(f, "wrap"),
+            Edges::Solid => write!(f, "solid"),
+        }
+    }
+}
+
+impl std::str::FromStr for Edges {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wrap" => Ok(Edges::Wrap),
+            "solid" => Ok(Edges::Solid),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How the time between steps shrinks as the score grows.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Difficulty {
+    #[default]
+    Classic,
+    /// Every point of score takes `per_score` seconds off the interval.
+    Linear { per_score: f32, min_interval: f32 },
+    /// Every `score_per_level` points multiply the interval by `factor`.
+    Stepped {
+        score_per_level: u32,
+        factor: f32,
+        min_interval: f32,
+    },
+}
+
+impl Difficulty {
+    pub const LINEAR: Difficulty = Difficulty::Linear {
+        per_score: 0.002,
+        min_interval: 0.05,
+    };
+    pub const STEPPED: Difficulty = Difficulty::Stepped {
+        score_per_level: 5,
+        factor: 0.85,
+        min_interval: 0.05,
+    };
+
+    pub fn level(&self, score: u32) -> u32 {
+        match self {
+            Difficulty::Classic => 1,
+            Difficulty::Linear { .. } => score + 1,
+            Difficulty::Stepped {
+                score_per_level, ..
+            } => score / (*score_per_level).max(1) + 1,
+        }
+    }
+
+    pub fn step_interval(&self, base: f32, score: u32) -> f32 {
+        match *self {
+            Difficulty::Classic => base,
+            Difficulty::Linear {
+                per_score,
+                min_interval,
+            } => (base - per_score * score as f32).max(min_interval.min(base)),
+            Difficulty::Stepped {
+                factor,
+                min_interval,
+                ..
+            } => {
+                let level = self.level(score) - 1;
+                (base * factor.powi(level as i32)).max(min_interval.min(base))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difficulty::Classic => write!(f, "classic"),
+            Difficulty::Linear { .. } => write!(f, "linear"),
+            Difficulty::Stepped { .. } => write!(f, "stepped"),
+        }
+    }
+}
+
+impl std::str::FromStr for Difficulty {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "classic" => Ok(Difficulty::Classic),
+            "linear" => Ok(Difficulty::LINEAR),
+            "stepped" => Ok(Difficulty::STEPPED),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Why a [`GameConfig`] cannot be played.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ConfigError {
+    /// A setting given by name has a value that does not parse.
+    InvalidValue {
+        name: String,
+        value: String,
+    },
+    /// The board has no room for the snakes to start.
+    BoardTooSmall {
+        cols: i32,
+        rows: i32,
+        min: i32,
+    },
+    CellSize(i32),
+    /// The board is too large to measure in pixels.
+    TooLarge {
+        cols: i32,
+        rows: i32,
+        cell_size: i32,
+    },
+    StepInterval(f32),
+    /// More players than the board has slots for, or none.
+    Players(usize),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::InvalidValue { name, value } => {
+                write!(f, "invalid `{}`: `{}`", name, value)
+            }
+            ConfigError::BoardTooSmall { cols, rows, min } => write!(
+                f,
+                "a {}x{} board is too small, it needs at least {} columns and rows",
+                cols, rows, min
+            ),
+            ConfigError::CellSize(cell_size) => {
+                write!(f, "cell size {} is not positive", cell_size)
+            }
+            ConfigError::TooLarge {
+                cols,
+                rows,
+                cell_size,
+            } => write!(
+                f,
+                "a {}x{} board of {} pixel cells is too large",
+                cols, rows, cell_size
+            ),
+            ConfigError::StepInterval(interval) => {
+                write!(f, "step interval {} is not a positive number", interval)
+            }
+            ConfigError::Players(players) => write!(
+                f,
+                "invalid `players`: `{}`, a board takes 1 to {}",
+                players, MAX_PLAYERS
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameConfig {
+    pub cols: i32,
+    pub rows: i32,
+    pub cell_size: i32,
+    pub snake_init_size: usize,
+    pub players: usize,
+    pub step_interval: f32,
+    pub edges: Edges,
+    pub difficulty: Difficulty,
+    pub level: Option<Arc<Level>>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            cols: 16,
+            rows: 9,
+            cell_size: 100,
+            snake_init_size: 3,
+            players: 1,
+            step_interval: 0.125,
+            edges: Edges::Wrap,
+            difficulty: Difficulty::Classic,
+            level: None,
+        }
+    }
+}
+
+impl GameConfig {
+    /// The names of the board settings [`GameConfig::with_params`] reads.
+    pub const PARAMS: [&str; 7] = [
+        "cols",
+        "rows",
+        "cell",
+        "edges",
+        "players",
+        "difficulty",
+        "level",
+    ];
+
+    /// Uses the level's walls and start, sizing the board to fit it.
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.cols = level.cols;
+        self.rows = level.rows;
+        self.level = Some(Arc::new(level));
+        self
+    }
+
+    /// The config with the board settings `param` gives by name, from the
+    /// query of the page, the config of a [`SnakeHandle`](crate::handle::SnakeHandle)
+    /// or the command line: `cols`, `rows`, `cell`, `edges`, `players`,
+    /// `difficulty` and `level`. Checked with [`GameConfig::validate`].
+    pub fn with_params(
+        mut self,
+        param: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        if let Some(cols) = parse_param(&param, "cols")? {
+            self.cols = cols;
+        }
+        if let Some(rows) = parse_param(&param, "rows")? {
+            self.rows = rows;
+        }
+        if let Some(cell_size) = parse_param(&param, "cell")? {
+            self.cell_size = cell_size;
+        }
+        if let Some(edges) = parse_param(&param, "edges")? {
+            self.edges = edges;
+        }
+        if let Some(players) = parse_param(&param, "players")? {
+            self.players = players;
+        }
+        if let Some(difficulty) = parse_param(&param, "difficulty")? {
+            self.difficulty = difficulty;
+        }
+        if let Some(name) = param("level") {
+            let level = Level::builtin(&name).ok_or_else(|| invalid_value("level", &name))?;
+            self = self.with_level(level);
+        }
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Checks that a round can be played on the board, for configs that come
+    /// from the page, the command line or a replay.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let min = self
+            .snake_init_size
+            .max(SNAKE_MIN_SIZE)
+            .min(i32::MAX as usize) as i32;
+        if self.cols < min || self.rows < min {
+            return Err(ConfigError::BoardTooSmall {
+                cols: self.cols,
+                rows: self.rows,
+                min,
+            });
+        }
+        if self.cell_size <= 0 {
+            return Err(ConfigError::CellSize(self.cell_size));
+        }
+        if self.cols.checked_mul(self.cell_size).is_none()
+            || self.rows.checked_mul(self.cell_size).is_none()
+            || self.cols.checked_mul(self.rows).is_none()
+        {
+            return Err(ConfigError::TooLarge {
+                cols: self.cols,
+                rows: self.rows,
+                cell_size: self.cell_size,
+            });
+        }
+        if !self.step_interval.is_finite() || self.step_interval <= 0.0 {
+            return Err(ConfigError::StepInterval(self.step_interval));
+        }
+        if !(1..=MAX_PLAYERS).contains(&self.players) {
+            return Err(ConfigError::Players(self.players));
+        }
+        Ok(())
+    }
+
+    pub fn width(&self) -> u32 {
+        (self.cols * self.cell_size) as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        (self.rows * self.cell_size) as u32
+    }
+
+    /// Spreads the starting rows of the players evenly over the board.
+    pub fn snake_init_row(&self, player: usize) -> i32 {
+        self.rows * (player as i32 + 1) / (self.players.clamp(1, MAX_PLAYERS) as i32 + 1)
+    }
+
+    pub fn contains(&self, cell: &Cell) -> bool {
+        (0..self.cols).contains(&cell.x) && (0..self.rows).contains(&cell.y)
+    }
+}
+
+fn invalid_value(name: &str, value: &str) -> ConfigError {
+    ConfigError::InvalidValue {
+        name: name.to_string(),
+        value: value.to_string(),
+    }
+}
+
+/// The setting `param` gives for `name`, if any.
+fn parse_param<T: std::str::FromStr>(
+    param: &impl Fn(&str) -> Option<String>,
+    name: &str,
+) -> Result<Option<T>, ConfigError> {
+    param(name)
+        .map(|value| value.parse().map_err(|_| invalid_value(name, &value)))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params<'a>(pairs: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        |name| {
+            pairs
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn step_interval_shrinks_with_the_score() {
+        for (difficulty, score, interval) in [
+            (Difficulty::Classic, 100, 0.125),
+            (Difficulty::LINEAR, 0, 0.125),
+            (Difficulty::LINEAR, 10, 0.105),
+            (Difficulty::LINEAR, 100, 0.05),
+            (Difficulty::STEPPED, 4, 0.125),
+            (Difficulty::STEPPED, 5, 0.10625),
+            (Difficulty::STEPPED, 10, 0.0903125),
+            (Difficulty::STEPPED, 100, 0.05),
+        ] {
+            let actual = difficulty.step_interval(0.125, score);
+            assert!(
+                (actual - interval).abs() < 1e-6,
+                "{} at {}: {}",
+                difficulty,
+                score,
+                actual
+            );
+        }
+        assert_eq!(Difficulty::STEPPED.level(10), 3);
+    }
+
+    #[test]
+    fn bad_boards_are_refused() {
+        assert_eq!(GameConfig::default().validate(), Ok(()));
+        for (cols, rows) in [(0, 9), (1, 9), (16, 2), (-16, 9)] {
+            let config = GameConfig {
+                cols,
+                rows,
+                ..GameConfig::default()
+            };
+            assert_eq!(
+                config.validate(),
+                Err(ConfigError::BoardTooSmall { cols, rows, min: 3 })
+            );
+        }
+        let config = GameConfig {
+            cell_size: 0,
+            ..GameConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::CellSize(0)));
+        let config = GameConfig {
+            cell_size: i32::MAX / 2,
+            ..GameConfig::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::TooLarge { .. })
+        ));
+        for step_interval in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            let config = GameConfig {
+                step_interval,
+                ..GameConfig::default()
+            };
+            assert!(matches!(
+                config.validate(),
+                Err(ConfigError::StepInterval(_))
+            ));
+        }
+        for players in [0, MAX_PLAYERS + 1] {
+            let config = GameConfig {
+                players,
+                ..GameConfig::default()
+            };
+            assert_eq!(config.validate(), Err(ConfigError::Players(players)));
+        }
+    }
+
+    #[test]
+    fn params_that_do_not_parse_are_reported() {
+        let config = GameConfig::default()
+            .with_params(params(&[("cols", "24"), ("edges", "solid")]))
+            .unwrap();
+        assert_eq!((config.cols, config.edges), (24, Edges::Solid));
+
+        assert_eq!(
+            GameConfig::default().with_params(params(&[("cols", "x")])),
+            Err(invalid_value("cols", "x"))
+        );
+        assert_eq!(
+            GameConfig::default().with_params(params(&[("level", "maze")])),
+            Err(invalid_value("level", "maze"))
+        );
+        assert_eq!(
+            GameConfig::default().with_params(params(&[("cols", "1")])),
+            Err(ConfigError::BoardTooSmall {
+                cols: 1,
+                rows: 9,
+                min: 3
+            })
+        );
+    }
+}
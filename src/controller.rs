@@ -0,0 +1,366 @@
+//! Bots that steer a snake in place of a human.
+//!
+//! A [`Controller`] is asked for a direction right before every step and only
+//! sees the board through a [`BoardView`], so the same bot can drive a player
+//! slot in the browser or play thousands of rounds headless.
+
+use std::{collections::VecDeque, fmt};
+
+use crate::{
+    config::{Edges, GameConfig},
+    food::Food,
+    simulation::{Cell, Direction, Input, Player, Simulation, State},
+};
+
+pub trait Controller {
+    fn next_dir(&mut self, board: &BoardView) -> Direction;
+}
+
+/// Read-only view of the board from the point of view of one player.
+pub struct BoardView<'a> {
+    sim: &'a Simulation,
+    player: usize,
+}
+
+impl<'a> BoardView<'a> {
+    pub fn new(sim: &'a Simulation, player: usize) -> Self {
+        Self { sim, player }
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        self.sim.config()
+    }
+
+    pub fn me(&self) -> &Player {
+        self.sim.player(self.player)
+    }
+
+    pub fn players(&self) -> &[Player] {
+        self.sim.players()
+    }
+
+    pub fn foods(&self) -> &[Food] {
+        self.sim.foods()
+    }
+
+    /// Whether moving the head onto the cell right now would not kill it.
+    pub fn is_safe(&self, cell: &Cell) -> bool {
+        self.config().contains(cell)
+            && !self.sim.is_wall(cell)
+            && !self
+                .players()
+                .iter()
+                .any(|player| player.is_alive() && player.snake().contains_cell(cell))
+    }
+
+    /// The directions the snake can actually turn to, skipping the reverse.
+    pub fn moves(&self) -> impl Iterator<Item = (Direction, Cell)> + '_ {
+        let head = *self.me().snake().head();
+        let dir = self.me().dir();
+        Direction::ALL
+            .into_iter()
+            .filter(move |next| *next != !dir)
+            .map(move |next| (next, head.advance(next, self.config())))
+    }
+
+    /// Foods worth steering towards.
+    pub fn targets(&self) -> impl Iterator<Item = &Food> + '_ {
+        self.foods().iter().filter(|food| food.kind.score() > 0)
+    }
+
+    pub fn distance(&self, a: &Cell, b: &Cell) -> i32 {
+        let config = self.config();
+        let (dx, dy) = ((a.x - b.x).abs(), (a.y - b.y).abs());
+        match config.edges {
+            Edges::Wrap => dx.min(config.cols - dx) + dy.min(config.rows - dy),
+            Edges::Solid => dx + dy,
+        }
+    }
+
+    fn index(&self, cell: &Cell) -> usize {
+        (cell.y * self.config().cols + cell.x) as usize
+    }
+
+    /// Number of safe cells reachable from `start`, counting `start` itself.
+    fn reachable_area(&self, start: &Cell) -> usize {
+        let mut visited = vec![false; (self.config().cols * self.config().rows) as usize];
+        let mut queue = VecDeque::from([*start]);
+        visited[self.index(start)] = true;
+        let mut area = 0;
+        while let Some(cell) = queue.pop_front() {
+            area += 1;
+            for dir in Direction::ALL {
+                let next = cell.advance(dir, self.config());
+                if self.is_safe(&next) && !visited[self.index(&next)] {
+                    visited[self.index(&next)] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        area
+    }
+
+    /// The safe move that leaves the most room, or the current direction if
+    /// every move is deadly.
+    pub fn roomiest_move(&self) -> Direction {
+        self.moves()
+            .filter(|(_, cell)| self.is_safe(cell))
+            .max_by_key(|(_, cell)| self.reachable_area(cell))
+            .map_or(self.me().dir(), |(dir, _)| dir)
+    }
+}
+
+/// Heads straight for the closest food, only looking one step ahead.
+#[derive(Default)]
+pub struct Greedy;
+
+impl Controller for Greedy {
+    fn next_dir(&mut self, board: &BoardView) -> Direction {
+        board
+            .moves()
+            .filter(|(_, cell)| board.is_safe(cell))
+            .min_by_key(|(_, cell)| {
+                board
+                    .targets()
+                    .map(|food| board.distance(cell, &food.cell))
+                    .min()
+                    .unwrap_or(0)
+            })
+            .map_or(board.me().dir(), |(dir, _)| dir)
+    }
+}
+
+/// Follows the shortest path to a food around walls and bodies.
+#[derive(Default)]
+pub struct Bfs;
+
+impl Controller for Bfs {
+    fn next_dir(&mut self, board: &BoardView) -> Direction {
+        let config = board.config();
+        let mut visited = vec![false; (config.cols * config.rows) as usize];
+        let mut queue = VecDeque::new();
+        for (dir, cell) in board.moves() {
+            if board.is_safe(&cell) && !visited[board.index(&cell)] {
+                visited[board.index(&cell)] = true;
+                queue.push_back((cell, dir));
+            }
+        }
+
+        while let Some((cell, first_dir)) = queue.pop_front() {
+            if board.targets().any(|food| food.cell == cell) {
+                return first_dir;
+            }
+            for dir in Direction::ALL {
+                let next = cell.advance(dir, config);
+                if board.is_safe(&next) && !visited[board.index(&next)] {
+                    visited[board.index(&next)] = true;
+                    queue.push_back((next, first_dir));
+                }
+            }
+        }
+
+        board.roomiest_move()
+    }
+}
+
+/// Walks a cycle through every cell of the board, so it never runs into
+/// itself. Boards without such a cycle (odd by odd, or with walls) fall back
+/// to [`Bfs`].
+#[derive(Default)]
+pub struct Hamiltonian {
+    cycle: Option<Cycle>,
+    fallback: Bfs,
+}
+
+struct Cycle {
+    cols: i32,
+    rows: i32,
+    /// Cells in the order they are visited.
+    order: Vec<Cell>,
+    /// Position of every cell in `order`, indexed by `y * cols + x`.
+    position: Vec<usize>,
+}
+
+impl Cycle {
+    /// Snakes through every row but the first column, then runs back up the
+    /// first column. Needs an even number of rows, so a board with an odd
+    /// number is handled transposed.
+    fn new(cols: i32, rows: i32) -> Option<Cycle> {
+        if cols < 2 || rows < 2 {
+            return None;
+        }
+        let transposed = rows % 2 != 0;
+        let (w, h) = if transposed {
+            (rows, cols)
+        } else {
+            (cols, rows)
+        };
+        if h % 2 != 0 {
+            return None;
+        }
+
+        let mut order = Vec::with_capacity((w * h) as usize);
+        for y in 0..h {
+            if y % 2 == 0 {
+                order.extend((1..w).map(|x| Cell { x, y }));
+            } else {
+                order.extend((1..w).rev().map(|x| Cell { x, y }));
+            }
+        }
+        order.extend((0..h).rev().map(|y| Cell { x: 0, y }));
+        if transposed {
+            for cell in &mut order {
+                *cell = Cell {
+                    x: cell.y,
+                    y: cell.x,
+                };
+            }
+        }
+
+        let mut position = vec![0; order.len()];
+        for (i, cell) in order.iter().enumerate() {
+            position[(cell.y * cols + cell.x) as usize] = i;
+        }
+        Some(Cycle {
+            cols,
+            rows,
+            order,
+            position,
+        })
+    }
+
+    fn next(&self, cell: &Cell) -> Cell {
+        let i = self.position[(cell.y * self.cols + cell.x) as usize];
+        self.order[(i + 1) % self.order.len()]
+    }
+}
+
+impl Controller for Hamiltonian {
+    fn next_dir(&mut self, board: &BoardView) -> Direction {
+        let config = board.config();
+        if config.level.is_some() {
+            return self.fallback.next_dir(board);
+        }
+        if self
+            .cycle
+            .as_ref()
+            .is_none_or(|cycle| (cycle.cols, cycle.rows) != (config.cols, config.rows))
+        {
+            self.cycle = Cycle::new(config.cols, config.rows);
+        }
+        let Some(cycle) = &self.cycle else {
+            return self.fallback.next_dir(board);
+        };
+
+        let head = board.me().snake().head();
+        let next = cycle.next(head);
+        match board.moves().find(|(_, cell)| *cell == next) {
+            Some((dir, cell)) if board.is_safe(&cell) => dir,
+            // Not on the cycle yet, e.g. right after a restart.
+            _ => board.roomiest_move(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Strategy {
+    Greedy,
+    Bfs,
+    Hamiltonian,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 3] = [Strategy::Greedy, Strategy::Bfs, Strategy::Hamiltonian];
+
+    pub fn controller(self) -> Box<dyn Controller> {
+        match self {
+            Strategy::Greedy => Box::new(Greedy),
+            Strategy::Bfs => Box::new(Bfs),
+            Strategy::Hamiltonian => Box::<Hamiltonian>::default(),
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strategy::Greedy => write!(f, "greedy"),
+            Strategy::Bfs => write!(f, "bfs"),
+            Strategy::Hamiltonian => write!(f, "hamiltonian"),
+        }
+    }
+}
+
+impl std::str::FromStr for Strategy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "greedy" => Ok(Strategy::Greedy),
+            "bfs" => Ok(Strategy::Bfs),
+            "hamiltonian" => Ok(Strategy::Hamiltonian),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Plays one round headless with a controller for every player, stopping at
+/// game over or after `max_ticks` steps.
+pub fn play_round(
+    config: GameConfig,
+    seed: u64,
+    controllers: &mut [Box<dyn Controller>],
+    max_ticks: u64,
+) -> Simulation {
+    let mut sim = Simulation::new(config, seed);
+    while sim.state() == State::GamePlay && sim.tick() < max_ticks {
+        let turns: Vec<Input> = controllers
+            .iter_mut()
+            .enumerate()
+            .take(sim.players().len())
+            .filter(|(player, _)| sim.player(*player).is_alive())
+            .map(|(player, controller)| {
+                Input::Turn(player, controller.next_dir(&BoardView::new(&sim, player)))
+            })
+            .collect();
+        for turn in turns {
+            sim.apply_input(turn);
+        }
+        sim.step();
+    }
+    sim
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strategies_never_turn_back() {
+        for strategy in Strategy::ALL {
+            let mut controller = strategy.controller();
+            for seed in 0..5 {
+                let mut sim = Simulation::new(GameConfig::default(), seed);
+                while sim.state() == State::GamePlay && sim.tick() < 300 {
+                    let dir = controller.next_dir(&BoardView::new(&sim, 0));
+                    assert_ne!(dir, !sim.player(0).dir(), "{} on seed {}", strategy, seed);
+                    sim.apply_input(Input::Turn(0, dir));
+                    sim.step();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bfs_reaches_the_food() {
+        for seed in 0..5 {
+            let sim = play_round(
+                GameConfig::default(),
+                seed,
+                &mut [Strategy::Bfs.controller()],
+                50,
+            );
+            assert!(sim.score() > 0, "seed {}", seed);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use crate::simulation::Cell;
+
+// Constants
+const GOLDEN_LIFETIME: u32 = 40;
+const BONUS_LIFETIME: u32 = 60;
+const SHRINK_SEGMENTS: i32 = 3;
+const SPEED_UP_FACTOR: f32 = 0.5;
+const SLOW_DOWN_FACTOR: f32 = 2.0;
+pub const SPEED_EFFECT_STEPS: u32 = 40;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FoodKind {
+    Normal,
+    Golden,
+    Shrink,
+    SpeedUp,
+    SlowDown,
+}
+
+impl FoodKind {
+    pub const ALL: [FoodKind; 5] = [
+        FoodKind::Normal,
+        FoodKind::Golden,
+        FoodKind::Shrink,
+        FoodKind::SpeedUp,
+        FoodKind::SlowDown,
+    ];
+
+    pub const BONUSES: [FoodKind; 4] = [
+        FoodKind::Golden,
+        FoodKind::Shrink,
+        FoodKind::SpeedUp,
+        FoodKind::SlowDown,
+    ];
+
+    /// The name of the kind in themes and saved games.
+    pub fn name(self) -> &'static str {
+        match self {
+            FoodKind::Normal => "normal",
+            FoodKind::Golden => "golden",
+            FoodKind::Shrink => "shrink",
+            FoodKind::SpeedUp => "speed_up",
+            FoodKind::SlowDown => "slow_down",
+        }
+    }
+
+    pub fn score(self) -> u32 {
+        match self {
+            FoodKind::Normal => 1,
+            FoodKind::Golden => 5,
+            FoodKind::Shrink => 0,
+            FoodKind::SpeedUp => 2,
+            FoodKind::SlowDown => 0,
+        }
+    }
+
+    /// How much longer the snake gets from eating the food.
+    pub fn growth(self) -> i32 {
+        match self {
+            FoodKind::Shrink => -SHRINK_SEGMENTS,
+            _ => 1,
+        }
+    }
+
+    /// Steps the food stays on the board, `None` if it never expires.
+    pub fn lifetime(self) -> Option<u32> {
+        match self {
+            FoodKind::Normal => None,
+            FoodKind::Golden => Some(GOLDEN_LIFETIME),
+            _ => Some(BONUS_LIFETIME),
+        }
+    }
+
+    /// Multiplier applied to the step interval for [`SPEED_EFFECT_STEPS`] steps.
+    pub fn speed_factor(self) -> Option<f32> {
+        match self {
+            FoodKind::SpeedUp => Some(SPEED_UP_FACTOR),
+            FoodKind::SlowDown => Some(SLOW_DOWN_FACTOR),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Food {
+    pub cell: Cell,
+    pub kind: FoodKind,
+    /// Steps left before the food disappears.
+    pub ttl: Option<u32>,
+    /// Whether the food appeared during the last step.
+    pub fresh: bool,
+}
+
+impl Food {
+    pub fn new(cell: Cell, kind: FoodKind) -> Self {
+        Self {
+            cell,
+            kind,
+            ttl: kind.lifetime(),
+            fresh: false,
+        }
+    }
+}
@@ -1,48 +1,47 @@
-use std::collections::VecDeque;
-
-use web_sys::console;
+use std::ops::RangeInclusive;
 
+#[cfg(feature = "dynamic_camera")]
+use crate::{camera::Camera, config::Edges};
 use crate::{
-    render::PlatformRenderer,
-    utils::{emod, lerpf, rand, ring_displace_back},
+    config::GameConfig,
+    controller::{BoardView, Controller},
+    food::FoodKind,
+    keymap::{Action, Keymap},
+    render::{Color, PlatformRenderer},
+    replay::Replay,
+    save::{SaveError, SavedGame},
+    scores::{MemoryScores, ScoreEntry, ScoreStore, board_key},
+    settings::SettingsStore,
+    simulation::{Cell, Direction, Input, MAX_PLAYERS, Simulation, State, StepEvent, Vec2},
+    theme::{BUILTIN_THEMES, Theme},
+    touch::Gesture,
+    utils::{Rng, lerpf, log},
 };
 
 // Constants
-const CELL_SIZE: i32 = 100;
-const COLS: i32 = 16;
-const ROWS: i32 = 9;
-const BACKGROUND_COLOR: u32 = 0xFF181818;
-const CELL1_COLOR: u32 = BACKGROUND_COLOR;
-const CELL2_COLOR: u32 = 0xFF183018;
-const SNAKE_HEAD_COLOR: u32 = 0xFF00FF00;
-const SNAKE_BODY_COLOR: u32 = 0xFF32CD32;
-const SNAKE_TAIL_COLOR: u32 = 0xFF228B22;
-const SNAKE_SPINE_COLOR: u32 = 0xFF006400;
-const EGG_BODY_COLOR: u32 = 0xFF31A6FF;
-const EGG_SPINE_COLOR: u32 = 0xFF3166BB;
+const WALL_INNER_PERCENT: f32 = 0.7;
+const FOOD_BLINK_STEPS: u32 = 10;
 const SNAKE_SPINE_THICKNESS_PERCENT: f32 = 0.05;
-const SNAKE_INIT_SIZE: usize = 3;
-const STEP_INTERVAL: f32 = 0.125;
-const SCORE_PADDING: i32 = 100;
-const SCORE_FONT_SIZE: u32 = 48;
-const SCORE_FONT_COLOR: u32 = 0xFFFFFFFF;
-const PAUSE_FONT_COLOR: u32 = SCORE_FONT_COLOR;
-const PAUSE_FONT_SIZE: u32 = SCORE_FONT_SIZE;
-const GAMEOVER_FONT_COLOR: u32 = SCORE_FONT_COLOR;
-const GAMEOVER_FONT_SIZE: u32 = SCORE_FONT_SIZE;
-const RANDOM_EGG_MAX_ATTEMPTS: u32 = 1000;
-const SNAKE_CAP: usize = (ROWS * COLS) as usize;
-const DIR_QUEUE_CAP: usize = 3 as usize;
-const SNAKE_INIT_ROW: i32 = ROWS / 2;
+const SCORE_FONT_SIZE_PERCENT: f32 = 0.48;
+const HUD_SMALL_FONT_SIZE_PERCENT: f32 = 0.32;
+const HUD_LINE_SPACING: f32 = 1.2;
+const PAUSE_FONT_SIZE_PERCENT: f32 = SCORE_FONT_SIZE_PERCENT;
+const GAMEOVER_FONT_SIZE_PERCENT: f32 = SCORE_FONT_SIZE_PERCENT;
 const DIR_LENS: usize = 4;
-const KEY_LEFT: &str = "a";
-const KEY_RIGHT: &str = "d";
-const KEY_UP: &str = "w";
-const KEY_DOWN: &str = "s";
-const KEY_ACCEPT: &str = " ";
-const KEY_RESTART: &str = "r";
-const GAMEOVER_EXPLOSION_RADIUS: f32 = 1000.0;
-const GAMEOVER_EXPLOSION_MAX_VEL: f32 = 200.0;
+const GAMEOVER_EXPLOSION_RADIUS_CELLS: f32 = 10.0;
+const GAMEOVER_EXPLOSION_MAX_VEL_CELLS: f32 = 2.0;
+const ATTRACT_RESTART_DELAY: f32 = 3.0;
+const KEYMAP_SETTING: &str = "keymap";
+const SAVE_SETTING: &str = "save";
+/// Steps one update may catch up on after a hitch, beyond which the game
+/// skips ahead rather than racing the snake across the board.
+const MAX_STEPS_PER_UPDATE: u32 = 5;
+/// Entries of the high-score table shown after a game over.
+const LEADERBOARD_LINES: usize = 5;
+#[cfg(feature = "dynamic_camera")]
+const CAMERA_ZOOM_STEP: f32 = 1.25;
+#[cfg(feature = "dynamic_camera")]
+const CAMERA_SHAKE_CELLS: f32 = 0.3;
 
 struct DeadSnake {
     items: Vec<Rect>,
@@ -50,91 +49,35 @@ struct DeadSnake {
     masks: Vec<u8>,
 }
 
-impl DeadSnake {
-    fn reset(&mut self) {
-        self.items.clear();
-        self.vels.clear();
-        self.masks.clear();
-    }
-}
-
-#[derive(Clone, Copy, PartialEq)]
-enum Direction {
-    Right = 0,
-    Up = 1,
-    Left = 2,
-    Down = 3,
-}
-
-impl Direction {
-    const ALL: [Direction; 4] = [
-        Direction::Right,
-        Direction::Up,
-        Direction::Left,
-        Direction::Down,
-    ];
+/// Something that happened in the game, for a frontend to report to its
+/// host, see [`Game::record_events`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameEvent {
+    /// A round started, or was restored, with the given seed.
+    Started(u64),
+    Paused,
+    Resumed,
+    Ate(usize, FoodKind),
+    Died(usize),
+    /// The round is over, with the winner of a game of several players.
+    Over(Option<usize>),
 }
 
-impl Into<Cell> for Direction {
-    fn into(self) -> Cell {
-        match self {
-            Direction::Right => Cell { x: 1, y: 0 },
-            Direction::Left => Cell { x: -1, y: 0 },
-            Direction::Up => Cell { x: 0, y: -1 },
-            Direction::Down => Cell { x: 0, y: 1 },
-        }
-    }
-}
-
-impl std::ops::Not for Direction {
-    type Output = Self;
-
-    fn not(self) -> Self::Output {
-        match self {
-            Direction::Right => Direction::Left,
-            Direction::Up => Direction::Down,
-            Direction::Left => Direction::Right,
-            Direction::Down => Direction::Up,
-        }
-    }
-}
-
-enum State {
-    GamePlay,
-    Pause,
-    GameOver,
-}
-
-#[derive(Default, Clone, Copy, PartialEq, Eq)]
-struct Vec2<I> {
-    x: I,
-    y: I,
-}
-
-impl<I> std::ops::Sub for Vec2<I>
-where
-    I: std::ops::Sub<Output = I>,
-{
-    type Output = Vec2<I>;
-
-    fn sub(self, rhs: Self) -> Self::Output {
-        Vec2 {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
-        }
-    }
+/// Presentation state of one player that the simulation knows nothing about.
+struct PlayerView {
+    eating_egg: bool,
+    eaten_kind: FoodKind,
+    eating_timer: f32,
+    dead_snake: Option<DeadSnake>,
 }
 
-impl<I> std::ops::Add for Vec2<I>
-where
-    I: std::ops::Add<Output = I>,
-{
-    type Output = Vec2<I>;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        Vec2 {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
+impl PlayerView {
+    fn new() -> Self {
+        Self {
+            eating_egg: false,
+            eaten_kind: FoodKind::Normal,
+            eating_timer: 0.0,
+            dead_snake: None,
         }
     }
 }
@@ -146,17 +89,6 @@ struct Rect {
     h: f32,
 }
 
-impl From<&Cell> for Rect {
-    fn from(cell: &Cell) -> Self {
-        Rect {
-            x: (cell.x * CELL_SIZE) as f32,
-            y: (cell.y * CELL_SIZE) as f32,
-            w: CELL_SIZE as f32,
-            h: CELL_SIZE as f32,
-        }
-    }
-}
-
 impl From<&Sides> for Rect {
     fn from(sides: &Sides) -> Self {
         Rect {
@@ -168,38 +100,6 @@ impl From<&Sides> for Rect {
     }
 }
 
-type Cell = Vec2<i32>;
-
-impl Cell {
-    fn determine_dir(&self, another: &Cell) -> Direction {
-        for dir in Direction::ALL {
-            if self.advance(dir) == *another {
-                return dir;
-            }
-        }
-        unreachable!()
-    }
-
-    fn advance(&self, dir: Direction) -> Cell {
-        let dir_cell: Cell = dir.into();
-        let mut res: Cell = dir_cell + *self;
-        res.wrap_by_game_size();
-        res
-    }
-
-    fn wrap_by_game_size(&mut self) {
-        self.x = emod(self.x, COLS);
-        self.y = emod(self.y, ROWS);
-    }
-
-    fn center(&self) -> Vec2<f32> {
-        Vec2 {
-            x: self.x as f32 * CELL_SIZE as f32 + CELL_SIZE as f32 / 2.0,
-            y: self.y as f32 * CELL_SIZE as f32 + CELL_SIZE as f32 / 2.0,
-        }
-    }
-}
-
 struct Sides {
     lens: Vec<f32>,
 }
@@ -238,488 +138,1000 @@ impl From<&Rect> for Sides {
     }
 }
 
-struct Snake {
-    items: VecDeque<Cell>,
-}
-
-impl Snake {
-    fn contains_cell(&self, cell: &Cell) -> bool {
-        self.items.contains(cell)
-    }
-
-    fn size(&self) -> usize {
-        self.items.len()
-    }
-}
-
 pub struct Game<P: PlatformRenderer> {
     width: u32,
     height: u32,
 
-    dir: Direction,
-    next_dirs: VecDeque<Direction>,
+    sim: Simulation,
+    rng: Rng,
+    /// `None` for a round picked up from a save, see [`Game::replay`].
+    replay: Option<Replay>,
 
-    state: State,
-    score: u32,
-    step_cooldown: f32,
-    eating_egg: bool,
+    views: Vec<PlayerView>,
+    #[cfg(not(feature = "dynamic_camera"))]
     camera_pos: Vec2<f32>,
-    eating_timer: f32,
-
-    body_start_color: u32,
-    body_end_color: u32,
+    #[cfg(feature = "dynamic_camera")]
+    camera: Camera,
+    game_over_timer: f32,
 
-    snake: Snake,
-    dead_snake: DeadSnake,
-    egg: Cell,
+    /// Bot for every player slot, `None` for human players.
+    controllers: Vec<Option<Box<dyn Controller>>>,
 
     platform_renderer: P,
+    theme: Theme,
+    keymap: Keymap,
+    settings: Option<Box<dyn SettingsStore>>,
+
+    scores: Box<dyn ScoreStore>,
+    /// The high-score table of the current board.
+    leaderboard: Vec<ScoreEntry>,
+    /// Ranks in `leaderboard` made this round, with the player who made each.
+    new_ranks: Vec<(usize, usize)>,
+    player_names: Vec<Option<String>>,
+    /// Seconds the current round has been played.
+    round_time: f32,
+    /// Events not yet taken, `None` unless a frontend asked for them.
+    events: Option<Vec<GameEvent>>,
 
     #[cfg(feature = "dev")]
     dt_scale: f32,
 }
 
 impl<P: PlatformRenderer> Game<P> {
-    pub fn new(platform_renderer: P) -> Self {
-        Self {
-            eating_timer: 0.0,
-            width: 0,
-            height: 0,
-            dir: Direction::Right,
-            state: State::GamePlay,
-            score: 0,
-            snake: Snake {
-                items: VecDeque::with_capacity(SNAKE_CAP),
-            },
+    pub fn new(platform_renderer: P, config: GameConfig, seed: u64) -> Self {
+        let mut game = Self {
+            width: config.width(),
+            height: config.height(),
+            sim: Simulation::new(config.clone(), seed),
+            rng: Rng::new(seed),
+            replay: Some(Replay::new(config, seed)),
+            #[cfg(not(feature = "dynamic_camera"))]
             camera_pos: Vec2::default(),
+            #[cfg(feature = "dynamic_camera")]
+            camera: Camera::new(Vec2::default(), false),
             platform_renderer,
-            eating_egg: false,
-            egg: Cell::default(),
-            step_cooldown: 0.0,
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+            settings: None,
+            scores: Box::new(MemoryScores::default()),
+            leaderboard: Vec::new(),
+            new_ranks: Vec::new(),
+            player_names: vec![None; MAX_PLAYERS],
+            round_time: 0.0,
+            events: None,
+            views: Vec::new(),
+            game_over_timer: 0.0,
+            controllers: (0..MAX_PLAYERS).map(|_| None).collect(),
             #[cfg(feature = "dev")]
             dt_scale: 0.0,
-            next_dirs: VecDeque::with_capacity(DIR_QUEUE_CAP),
-            dead_snake: DeadSnake {
-                items: Vec::new(),
-                vels: Vec::new(),
-                masks: Vec::new(),
-            },
-            body_start_color: 0xFF00FF00,
-            body_end_color: 0xFF0000FF,
-        }
+        };
+        game.reset_view();
+        game
     }
 
-    fn reset(&mut self) {
-        let platform_renderer = self.platform_renderer.clone();
-        *self = Self::new(platform_renderer);
+    /// Handles a key press by its `KeyboardEvent.code` and `key`, see [`Keymap`].
+    pub fn keydown(&mut self, code: &str, key: &str) {
+        self.press(self.keymap.action(code, key));
     }
 
-    pub fn keydown(&mut self, key: &str) {
-        #[cfg(feature = "dev")]
+    /// Swipes steer the first snake and taps pause, or start a new round.
+    pub fn gesture(&mut self, gesture: Gesture) {
+        self.press(Some(match gesture {
+            Gesture::Swipe(dir) => Action::Turn(0, dir),
+            Gesture::Tap => Action::Pause,
+        }));
+    }
+
+    /// A press of a key, button or the screen, bound to `action` if any.
+    pub fn press(&mut self, action: Option<Action>) {
+        if let Some(action) = action
+            && !action.is_gameplay()
         {
-            const DEV_DT_SCALE_STEP: f32 = 0.05;
-            match key {
-                "z" => {
-                    self.dt_scale -= DEV_DT_SCALE_STEP;
-                    if self.dt_scale < 0.0 {
-                        self.dt_scale = 0.0;
-                    }
-                    console::log_1(&format!("dt scale = {}", self.dt_scale).into());
-                }
-                "x" => {
-                    self.dt_scale += DEV_DT_SCALE_STEP;
-                    console::log_1(&format!("dt scale = {}", self.dt_scale).into());
-                }
-                "c" => {
-                    self.dt_scale = 1.0;
-                    console::log_1(&format!("dt scale = {}", self.dt_scale).into());
+            self.action(action);
+            return;
+        }
+
+        // Any other key starts a new round.
+        if self.sim.state() == State::GameOver {
+            self.apply_input(Input::Restart);
+            return;
+        }
+
+        if let Some(action) = action {
+            self.action(action);
+        }
+    }
+
+    /// Performs an action, whichever key, button or gesture it came from.
+    pub fn action(&mut self, action: Action) {
+        match action {
+            Action::Turn(slot, dir) => {
+                if let Some(player) = self.turn_player(slot) {
+                    self.apply_input(Input::Turn(player, dir));
                 }
-                _ => {}
             }
+            Action::Pause => self.apply_input(Input::TogglePause),
+            Action::Restart => self.apply_input(Input::Restart),
+            Action::NextTheme => self.next_theme(),
+            Action::ZoomIn | Action::ZoomOut | Action::ZoomReset => self.zoom_action(action),
+            Action::DevSlower
+            | Action::DevFaster
+            | Action::DevNormalSpeed
+            | Action::DevLogReplay => self.dev_action(action),
         }
+    }
 
-        match self.state {
-            State::GamePlay => match key {
-                KEY_UP => {
-                    ring_displace_back(&mut self.next_dirs, Direction::Up, DIR_QUEUE_CAP);
-                }
-                KEY_DOWN => {
-                    ring_displace_back(&mut self.next_dirs, Direction::Down, DIR_QUEUE_CAP);
-                }
-                KEY_LEFT => {
-                    ring_displace_back(&mut self.next_dirs, Direction::Left, DIR_QUEUE_CAP);
-                }
-                KEY_RIGHT => {
-                    ring_displace_back(&mut self.next_dirs, Direction::Right, DIR_QUEUE_CAP);
-                }
-                KEY_ACCEPT => {
-                    self.state = State::Pause;
-                }
-                KEY_RESTART => {
-                    self.restart(self.width, self.height);
+    /// The human player steered by the turn keys of `slot`. Keys of a slot
+    /// without a human snake, like the arrows in a one player game, steer the
+    /// first human snake instead.
+    fn turn_player(&self, slot: usize) -> Option<usize> {
+        let players = self.sim.players().len();
+        let human = |player: usize| player < players && self.controllers[player].is_none();
+        if human(slot) {
+            Some(slot)
+        } else {
+            (0..players).find(|player| human(*player))
+        }
+    }
+
+    #[cfg(feature = "dynamic_camera")]
+    fn zoom_action(&mut self, action: Action) {
+        let zoom = match action {
+            Action::ZoomIn => self.camera.target_zoom() * CAMERA_ZOOM_STEP,
+            Action::ZoomOut => self.camera.target_zoom() / CAMERA_ZOOM_STEP,
+            _ => 1.0,
+        };
+        self.set_zoom(zoom);
+    }
+
+    #[cfg(not(feature = "dynamic_camera"))]
+    fn zoom_action(&mut self, _action: Action) {}
+
+    #[cfg(feature = "dev")]
+    fn dev_action(&mut self, action: Action) {
+        const DEV_DT_SCALE_STEP: f32 = 0.05;
+        match action {
+            Action::DevSlower => {
+                self.dt_scale -= DEV_DT_SCALE_STEP;
+                if self.dt_scale < 0.0 {
+                    self.dt_scale = 0.0;
                 }
-                _ => {}
+                log(&format!("dt scale = {}", self.dt_scale));
+            }
+            Action::DevFaster => {
+                self.dt_scale += DEV_DT_SCALE_STEP;
+                log(&format!("dt scale = {}", self.dt_scale));
+            }
+            Action::DevNormalSpeed => {
+                self.dt_scale = 1.0;
+                log(&format!("dt scale = {}", self.dt_scale));
+            }
+            _ => match self.replay() {
+                Some(replay) => log(&replay.to_string()),
+                None => log("no replay of a round picked up from a save"),
             },
-            State::Pause => match key {
-                KEY_ACCEPT => {
-                    self.state = State::GamePlay;
+        }
+    }
+
+    #[cfg(not(feature = "dev"))]
+    fn dev_action(&mut self, _action: Action) {}
+
+    pub fn apply_input(&mut self, input: Input) {
+        let state = self.sim.state();
+        if let Some(replay) = &mut self.replay {
+            replay.record(self.sim.tick(), input);
+        }
+        self.sim.apply_input(input);
+        if input == Input::Restart && self.replay.is_none() {
+            self.replay = Some(Replay::new(self.config().clone(), self.sim.seed()));
+        }
+        if input == Input::Restart {
+            self.reset_view();
+            self.emit(GameEvent::Started(self.sim.seed()));
+        } else if state != self.sim.state() {
+            self.emit(match self.sim.state() {
+                State::Pause => GameEvent::Paused,
+                _ => GameEvent::Resumed,
+            });
+        }
+    }
+
+    /// Starts keeping the events of the game until they are taken, for a
+    /// frontend that takes them every frame.
+    pub fn record_events(&mut self) {
+        self.events.get_or_insert_with(Vec::new);
+    }
+
+    /// The events since they were last taken, oldest first.
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        self.events.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        if let Some(events) = &mut self.events {
+            events.push(event);
+        }
+    }
+
+    /// Pauses a round in play, and leaves the game alone otherwise.
+    pub fn pause(&mut self) {
+        if self.sim.state() == State::GamePlay {
+            self.apply_input(Input::TogglePause);
+        }
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Switches to the built-in theme after the current one.
+    fn next_theme(&mut self) {
+        let next = self
+            .theme
+            .builtin_name()
+            .and_then(|name| BUILTIN_THEMES.iter().position(|(n, _)| *n == name))
+            .map_or(0, |i| (i + 1) % BUILTIN_THEMES.len());
+        let (name, _) = BUILTIN_THEMES[next];
+        self.theme = Theme::builtin(name).unwrap();
+        log(&format!("theme = {}", name));
+    }
+
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    /// Replaces the keymap and saves it to the settings store, if there is one.
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        if let Some(settings) = &mut self.settings {
+            settings.save(KEYMAP_SETTING, &keymap.to_string());
+        }
+        self.keymap = keymap;
+    }
+
+    /// Keeps settings in `store` from now on, starting with the keymap saved there.
+    pub fn set_settings_store(&mut self, store: Box<dyn SettingsStore>) {
+        if let Some(text) = store.load(KEYMAP_SETTING) {
+            match text.parse() {
+                Ok(keymap) => self.keymap = keymap,
+                Err(err) => log(&format!("saved keymap: {}", err)),
+            }
+        }
+        self.settings = Some(store);
+    }
+
+    /// Keeps high scores in `store` from now on.
+    pub fn set_score_store(&mut self, store: Box<dyn ScoreStore>) {
+        self.scores = store;
+        self.leaderboard = self.scores.scores(&board_key(self.config()));
+    }
+
+    /// The high-score table of the current board, best first.
+    pub fn leaderboard(&self) -> &[ScoreEntry] {
+        &self.leaderboard
+    }
+
+    /// Sets the name high scores of `player` go under, or back to the
+    /// default with an empty one. Players past [`MAX_PLAYERS`] are ignored.
+    pub fn set_player_name(&mut self, player: usize, name: &str) {
+        let Some(slot) = self.player_names.get_mut(player) else {
+            return;
+        };
+        let name: String = name.chars().filter(|c| !c.is_control()).collect();
+        let name = name.trim();
+        *slot = (!name.is_empty()).then(|| name.to_string());
+    }
+
+    pub fn player_name(&self, player: usize) -> String {
+        self.player_names
+            .get(player)
+            .cloned()
+            .flatten()
+            .unwrap_or_else(|| format!("Player {}", player + 1))
+    }
+
+    /// Puts the scores of the human players of the round that just ended in
+    /// the high-score table.
+    fn record_scores(&mut self) {
+        let board = board_key(self.config());
+        for player in 0..self.sim.players().len() {
+            let score = self.sim.player(player).score();
+            if self.controllers[player].is_some() || score == 0 {
+                continue;
+            }
+            let entry = ScoreEntry {
+                name: self.player_name(player),
+                score,
+                length: self.sim.player(player).snake().items.len(),
+                duration: self.round_time,
+                seed: self.sim.seed(),
+            };
+            if let Some(rank) = self.scores.add(&board, entry) {
+                for (other, _) in &mut self.new_ranks {
+                    if *other >= rank {
+                        *other += 1;
+                    }
                 }
-                KEY_RESTART => {
-                    self.restart(self.width, self.height);
+                self.new_ranks.push((rank, player));
+            }
+        }
+        self.leaderboard = self.scores.scores(&board);
+    }
+
+    /// Hands a player slot to a bot, or back to the keyboard with `None`.
+    /// Slots past [`MAX_PLAYERS`] are ignored.
+    pub fn set_controller(&mut self, player: usize, controller: Option<Box<dyn Controller>>) {
+        if let Some(slot) = self.controllers.get_mut(player) {
+            *slot = controller;
+        }
+    }
+
+    fn all_bots(&self) -> bool {
+        self.controllers
+            .iter()
+            .take(self.sim.players().len())
+            .all(Option::is_some)
+    }
+
+    fn bots_input(&mut self) {
+        let mut turns = Vec::new();
+        for (player, controller) in self.controllers.iter_mut().enumerate() {
+            if let Some(controller) = controller
+                && player < self.sim.players().len()
+                && self.sim.player(player).is_alive()
+            {
+                let dir = controller.next_dir(&BoardView::new(&self.sim, player));
+                if dir != self.sim.player(player).dir() {
+                    turns.push(Input::Turn(player, dir));
                 }
-                _ => {}
-            },
-            State::GameOver => {
-                self.restart(self.width, self.height);
             }
         }
+        for turn in turns {
+            self.apply_input(turn);
+        }
     }
 
-    pub fn restart(&mut self, width: u32, height: u32) {
-        self.reset();
-        self.width = width;
-        self.height = height;
+    pub fn restart(&mut self) {
+        self.apply_input(Input::Restart);
+    }
 
-        #[cfg(feature = "dev")]
-        {
-            self.dt_scale = 1.0;
+    pub fn restart_with_seed(&mut self, seed: u64) {
+        self.restart_with_config(self.config().clone(), seed);
+    }
+
+    pub fn restart_with_config(&mut self, config: GameConfig, seed: u64) {
+        self.width = config.width();
+        self.height = config.height();
+        self.sim.restart_with_config(config.clone(), seed);
+        self.replay = Some(Replay::new(config, seed));
+        self.reset_view();
+        self.emit(GameEvent::Started(seed));
+    }
+
+    /// The round in progress in the text form of [`crate::save`].
+    pub fn save(&self) -> String {
+        let mut saved = self.sim.to_saved();
+        saved.time = self.round_time;
+        saved.to_string()
+    }
+
+    /// Carries on with a round from [`Game::save`], played on the current
+    /// board. There is no replay of it, as no inputs lead up to it.
+    pub fn restore(&mut self, save: &str) -> Result<(), SaveError> {
+        self.restore_saved(&save.parse()?)
+    }
+
+    fn restore_saved(&mut self, saved: &SavedGame) -> Result<(), SaveError> {
+        self.sim = Simulation::from_saved(self.config().clone(), saved)?;
+        self.replay = None;
+        self.reset_view();
+        self.round_time = saved.time;
+        self.emit(GameEvent::Started(saved.seed));
+        Ok(())
+    }
+
+    /// Saves the round in progress to the settings store, if there is one,
+    /// or forgets the last save once there is no round worth picking up.
+    pub fn autosave(&mut self) {
+        let save = (self.sim.state() != State::GameOver && !self.all_bots()).then(|| self.save());
+        let Some(settings) = &mut self.settings else {
+            return;
+        };
+        match save {
+            Some(save) => settings.save(SAVE_SETTING, &save),
+            None => settings.remove(SAVE_SETTING),
         }
+    }
 
-        self.camera_pos.x = width as f32 / 2.0;
-        self.camera_pos.y = height as f32 / 2.0;
+    fn autosaved(&self) -> Option<SavedGame> {
+        self.settings.as_ref()?.load(SAVE_SETTING)?.parse().ok()
+    }
 
-        self.state = State::GamePlay;
-        self.dir = Direction::Right;
-        self.score = 0;
+    /// Whether the settings store has a round of this board to pick up.
+    pub fn can_resume(&self) -> bool {
+        self.autosaved()
+            .is_some_and(|saved| Simulation::from_saved(self.config().clone(), &saved).is_ok())
+    }
 
-        for i in 0..SNAKE_INIT_SIZE {
-            let head = Cell {
-                x: i as i32,
-                y: SNAKE_INIT_ROW,
-            };
-            self.snake.items.push_back(head);
+    /// Picks up the auto-saved round, paused, and tells whether there was one.
+    pub fn resume(&mut self) -> bool {
+        let Some(save) = self.autosaved() else {
+            return false;
+        };
+        if self.restore_saved(&save).is_err() {
+            return false;
         }
-        self.dead_snake.reset();
+        self.pause();
+        true
+    }
 
-        self.random_egg(true);
+    /// The session recorded so far, ending at the current tick, or `None` for
+    /// a round picked up from a save until the next round starts.
+    pub fn replay(&self) -> Option<Replay> {
+        let replay = self.replay.as_ref()?;
+        Some(Replay {
+            end_tick: self.sim.tick(),
+            ..replay.clone()
+        })
     }
 
-    pub fn update(&mut self, dt: f32) {
-        let mut dt = dt;
+    fn reset_view(&mut self) {
         #[cfg(feature = "dev")]
         {
-            dt *= self.dt_scale;
+            self.dt_scale = 1.0;
+        }
+
+        #[cfg(not(feature = "dynamic_camera"))]
+        {
+            self.camera_pos.x = self.width as f32 / 2.0;
+            self.camera_pos.y = self.height as f32 / 2.0;
         }
+        #[cfg(feature = "dynamic_camera")]
+        self.camera.reset(
+            Vec2 {
+                x: self.width as f32,
+                y: self.height as f32,
+            },
+            self.config().edges == Edges::Wrap,
+        );
+
+        self.views = self
+            .sim
+            .players()
+            .iter()
+            .map(|_| PlayerView::new())
+            .collect();
+        self.game_over_timer = 0.0;
+        self.rng = Rng::new(self.sim.seed());
+        self.round_time = 0.0;
+        self.new_ranks.clear();
+        self.leaderboard = self.scores.scores(&board_key(self.config()));
+    }
+
+    pub fn simulation(&self) -> &Simulation {
+        &self.sim
+    }
 
-        if self.eating_egg {
-            self.eating_timer += dt;
-            if self.eating_timer > 1.0 {
-                self.eating_egg = false;
-                self.eating_timer = 0.0;
+    pub fn config(&self) -> &GameConfig {
+        self.sim.config()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Zooms the camera in (above 1) or out (below 1), easing towards it.
+    #[cfg(feature = "dynamic_camera")]
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.camera.set_zoom(zoom);
+    }
+
+    /// Where the camera heads: the sliding head of the first living snake.
+    #[cfg(feature = "dynamic_camera")]
+    fn camera_target(&self) -> Option<Vec2<f32>> {
+        let player = self.sim.players().iter().find(|player| player.is_alive())?;
+        let t = 1.0 - self.sim.step_alpha();
+        let head = self.cell_center(player.snake().head());
+        let back: Cell = (!player.dir()).into();
+        Some(Vec2 {
+            x: head.x + back.x as f32 * self.cell_size() * t,
+            y: head.y + back.y as f32 * self.cell_size() * t,
+        })
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        #[cfg(feature = "dev")]
+        let dt = dt * self.dt_scale;
+
+        for view in &mut self.views {
+            if view.eating_egg {
+                view.eating_timer += dt;
+                if view.eating_timer > 1.0 {
+                    view.eating_egg = false;
+                    view.eating_timer = 0.0;
+                }
             }
         }
 
-        match self.state {
-            State::GamePlay => {
-                self.step_cooldown -= dt;
-                if self.step_cooldown > 0.0 {
+        #[cfg(feature = "dynamic_camera")]
+        self.camera.update(self.camera_target(), dt);
+
+        match self.sim.state() {
+            State::Pause => return,
+            // Keep an attract-mode demo running on its own.
+            State::GameOver if self.all_bots() => {
+                self.game_over_timer += dt;
+                if self.game_over_timer > ATTRACT_RESTART_DELAY {
+                    self.restart();
                     return;
                 }
+            }
+            _ => {}
+        }
 
-                if !self.next_dirs.is_empty() {
-                    if !self.dir != *self.next_dirs.front().unwrap() {
-                        self.dir = *self.next_dirs.front().unwrap();
-                    }
-                    self.next_dirs.pop_front();
+        for view in &mut self.views {
+            if let Some(dead_snake) = &mut view.dead_snake {
+                for i in 1..dead_snake.items.len() {
+                    dead_snake.vels[i].x *= 0.99;
+                    dead_snake.vels[i].y *= 0.99;
+                    dead_snake.items[i].x += dead_snake.vels[i].x * dt;
+                    dead_snake.items[i].y += dead_snake.vels[i].y * dt;
                 }
+            }
+        }
 
-                let next_head = self.snake.items.back().unwrap().advance(self.dir);
+        if self.sim.state() == State::GamePlay {
+            self.round_time += dt;
+        }
+        self.sim.advance_timer(dt);
+        let mut steps = 0;
+        while self.sim.step_due() {
+            if steps == MAX_STEPS_PER_UPDATE {
+                self.sim.skip_due_steps();
+                break;
+            }
+            self.step();
+            steps += 1;
+        }
+    }
 
-                if next_head == self.egg {
-                    self.snake.items.push_back(next_head);
-                    self.random_egg(false);
-                    self.eating_egg = true;
-                    self.score += 1;
-                } else {
-                    if self.snake.contains_cell(&next_head) {
-                        self.step_cooldown = 0.0;
-                        self.state = State::GameOver;
-                        self.init_dead_snake(&next_head);
-                        return;
-                    } else {
-                        self.snake.items.push_back(next_head);
-                        self.snake.items.pop_front();
-                        self.eating_egg = false;
-                    }
+    /// Lets the bots pick a direction and takes one step of the simulation.
+    fn step(&mut self) {
+        self.bots_input();
+        for event in self.sim.step().unwrap_or_default() {
+            match event {
+                StepEvent::Ate(player, kind) => {
+                    self.views[player].eating_egg = kind.growth() > 0;
+                    self.views[player].eaten_kind = kind;
+                    self.emit(GameEvent::Ate(player, kind));
                 }
-
-                self.step_cooldown = STEP_INTERVAL;
-            }
-            State::Pause => {}
-            State::GameOver => {
-                for i in 1..self.dead_snake.items.len() {
-                    self.dead_snake.vels[i].x *= 0.99;
-                    self.dead_snake.vels[i].y *= 0.99;
-                    self.dead_snake.items[i].x += self.dead_snake.vels[i].x * dt;
-                    self.dead_snake.items[i].y += self.dead_snake.vels[i].y * dt;
+                StepEvent::Moved(player) => self.views[player].eating_egg = false,
+                StepEvent::Died(player, next_head) => {
+                    self.init_dead_snake(player, &next_head);
+                    self.emit(GameEvent::Died(player));
                 }
             }
         }
+
+        if self.sim.state() == State::GameOver {
+            self.emit(GameEvent::Over(self.sim.winner()));
+            self.record_scores();
+            #[cfg(feature = "dynamic_camera")]
+            self.camera.shake(self.cell_size() * CAMERA_SHAKE_CELLS);
+        }
     }
 
-    fn score_text(&self) -> String {
-        format!("Score: {}", self.score)
+    fn cell_size(&self) -> f32 {
+        self.config().cell_size as f32
+    }
+
+    fn hud_padding(&self) -> i32 {
+        self.config().cell_size
+    }
+
+    fn font_size(&self, percent: f32) -> u32 {
+        (self.cell_size() * percent) as u32
+    }
+
+    fn cell_rect(&self, cell: &Cell) -> Rect {
+        Rect {
+            x: cell.x as f32 * self.cell_size(),
+            y: cell.y as f32 * self.cell_size(),
+            w: self.cell_size(),
+            h: self.cell_size(),
+        }
+    }
+
+    fn cell_center(&self, cell: &Cell) -> Vec2<f32> {
+        Vec2 {
+            x: cell.x as f32 * self.cell_size() + self.cell_size() / 2.0,
+            y: cell.y as f32 * self.cell_size() + self.cell_size() / 2.0,
+        }
+    }
+
+    fn score_text(&self, player: usize) -> String {
+        if self.sim.players().len() == 1 {
+            format!("Score: {}", self.sim.score())
+        } else {
+            format!("P{}: {}", player + 1, self.sim.player(player).score())
+        }
+    }
+
+    fn game_over_text(&self) -> String {
+        match self.sim.winner() {
+            _ if self.sim.players().len() == 1 => "Game Over".to_string(),
+            Some(player) => format!("Player {} wins", player + 1),
+            None => "Draw".to_string(),
+        }
+    }
+
+    fn speed_text(&self) -> String {
+        format!(
+            "Level {}  {:.1} steps/s",
+            self.sim.level(),
+            1.0 / self.sim.step_interval()
+        )
+    }
+
+    fn hud_render<R: PlatformRenderer>(&self, renderer: &R) {
+        let font_size = self.font_size(SCORE_FONT_SIZE_PERCENT);
+        let line_height = (font_size as f32 * HUD_LINE_SPACING) as i32;
+        let players = self.sim.players().len();
+        for (player, colors) in self.theme.players.iter().enumerate().take(players) {
+            let color = if players == 1 {
+                self.theme.text
+            } else {
+                colors.head
+            };
+            self.fill_text(
+                renderer,
+                self.hud_padding(),
+                self.hud_padding() + line_height * player as i32,
+                &self.score_text(player),
+                font_size,
+                color,
+            );
+        }
+        self.fill_text(
+            renderer,
+            self.hud_padding(),
+            self.hud_padding() + line_height * players as i32,
+            &self.speed_text(),
+            self.font_size(HUD_SMALL_FONT_SIZE_PERCENT),
+            self.theme.text,
+        );
+        if let Some(best) = self.leaderboard.first() {
+            let small_line_height =
+                (self.font_size(HUD_SMALL_FONT_SIZE_PERCENT) as f32 * HUD_LINE_SPACING) as i32;
+            self.fill_text(
+                renderer,
+                self.hud_padding(),
+                self.hud_padding() + line_height * players as i32 + small_line_height,
+                &format!("Best: {}", best.score.max(self.sim.score())),
+                self.font_size(HUD_SMALL_FONT_SIZE_PERCENT),
+                self.theme.text,
+            );
+        }
+    }
+
+    /// The top of the high-score table, under the game over text, with the
+    /// entries of this round in the colour of their player.
+    fn leaderboard_render<R: PlatformRenderer>(&self, renderer: &R) {
+        let font_size = self.font_size(HUD_SMALL_FONT_SIZE_PERCENT);
+        let line_height = (font_size as f32 * HUD_LINE_SPACING) as i32;
+        let top = self.height as i32 / 2 + self.font_size(GAMEOVER_FONT_SIZE_PERCENT) as i32;
+        for (rank, entry) in self.leaderboard.iter().take(LEADERBOARD_LINES).enumerate() {
+            let color = match self.new_ranks.iter().find(|(new, _)| *new == rank) {
+                Some((_, player)) => self.theme.players[*player].head,
+                None => self.theme.text,
+            };
+            self.fill_text(
+                renderer,
+                self.width as i32 / 2,
+                top + line_height * rank as i32,
+                &format!(
+                    "{}. {}  {}  ({:.0}s)",
+                    rank + 1,
+                    entry.name,
+                    entry.score,
+                    entry.duration
+                ),
+                font_size,
+                color,
+            );
+        }
     }
 
     pub fn render(&self) {
-        match self.state {
+        self.render_to(&self.platform_renderer);
+    }
+
+    /// Draws the current frame on any renderer, e.g. to export it.
+    pub fn render_to<R: PlatformRenderer>(&self, renderer: &R) {
+        match self.sim.state() {
             State::GamePlay => {
-                self.background_render();
-                self.egg_render();
-                self.snake_render();
-                self.fill_text(
-                    SCORE_PADDING,
-                    SCORE_PADDING,
-                    &self.score_text(),
-                    SCORE_FONT_SIZE,
-                    SCORE_FONT_COLOR,
-                );
+                self.background_render(renderer);
+                self.foods_render(renderer);
+                self.snakes_render(renderer);
+                self.hud_render(renderer);
             }
             State::Pause => {
-                self.background_render();
-                self.egg_render();
-                self.snake_render();
+                self.background_render(renderer);
+                self.foods_render(renderer);
+                self.snakes_render(renderer);
+                self.hud_render(renderer);
                 self.fill_text(
-                    SCORE_PADDING,
-                    SCORE_PADDING,
-                    &self.score_text(),
-                    SCORE_FONT_SIZE,
-                    SCORE_FONT_COLOR,
-                );
-                self.fill_text(
-                    self.camera_pos.x as i32,
-                    self.camera_pos.y as i32,
+                    renderer,
+                    self.width as i32 / 2,
+                    self.height as i32 / 2,
                     "Pause",
-                    PAUSE_FONT_SIZE,
-                    PAUSE_FONT_COLOR,
+                    self.font_size(PAUSE_FONT_SIZE_PERCENT),
+                    self.theme.text,
                 );
             }
             State::GameOver => {
-                self.background_render();
-                self.egg_render();
-                self.dead_snake_render();
-                self.fill_text(
-                    SCORE_PADDING,
-                    SCORE_PADDING,
-                    &self.score_text(),
-                    SCORE_FONT_SIZE,
-                    SCORE_FONT_COLOR,
-                );
+                self.background_render(renderer);
+                self.foods_render(renderer);
+                self.snakes_render(renderer);
+                self.hud_render(renderer);
                 self.fill_text(
-                    self.camera_pos.x as i32,
-                    self.camera_pos.y as i32,
-                    "Game Over",
-                    GAMEOVER_FONT_SIZE,
-                    GAMEOVER_FONT_COLOR,
+                    renderer,
+                    self.width as i32 / 2,
+                    self.height as i32 / 2,
+                    &self.game_over_text(),
+                    self.font_size(GAMEOVER_FONT_SIZE_PERCENT),
+                    self.theme.text,
                 );
+                self.leaderboard_render(renderer);
             }
         }
 
         #[cfg(feature = "dev")]
         {
             self.fill_text(
-                self.width as i32 - SCORE_PADDING * 5,
-                SCORE_PADDING,
+                renderer,
+                self.width as i32 - self.hud_padding() * 5,
+                self.hud_padding(),
                 "Dev",
-                SCORE_FONT_SIZE,
-                SCORE_FONT_COLOR,
+                self.font_size(SCORE_FONT_SIZE_PERCENT),
+                self.theme.text,
             );
             self.stroke_rect(
+                renderer,
                 Rect {
                     x: 0.0,
                     y: 0.0,
-                    w: (COLS * CELL_SIZE) as f32,
-                    h: (ROWS * CELL_SIZE) as f32,
+                    w: self.config().width() as f32,
+                    h: self.config().height() as f32,
                 },
-                0xFF0000FF,
+                Color::RED,
             );
         }
     }
 
-    fn dead_snake_render(&self) {
-        for i in 1..self.dead_snake.items.len() {
-            self.fill_rect(self.dead_snake.items.get(i).unwrap(), SNAKE_BODY_COLOR);
-            self.fill_fractured_spine(
-                self.dead_snake.items.get(i).unwrap().into(),
-                *self.dead_snake.masks.get(i).unwrap(),
-            );
+    fn snakes_render<R: PlatformRenderer>(&self, renderer: &R) {
+        for (i, player) in self.sim.players().iter().enumerate() {
+            if player.is_alive() {
+                self.snake_render(renderer, i);
+            } else {
+                self.dead_snake_render(renderer, i);
+            }
         }
     }
 
-    fn lerp_color(&self, color1: u32, color2: u32, t: f32) -> u32 {
-        let r1 = (color1 >> 16) & 0xFF;
-        let g1 = (color1 >> 8) & 0xFF;
-        let b1 = color1 & 0xFF;
-        let r2 = (color2 >> 16) & 0xFF;
-        let g2 = (color2 >> 8) & 0xFF;
-        let b2 = color2 & 0xFF;
-        let r = (r1 as f32 + (r2 as f32 - r1 as f32) * t).round() as u32;
-        let g = (g1 as f32 + (g2 as f32 - g1 as f32) * t).round() as u32;
-        let b = (b1 as f32 + (b2 as f32 - b1 as f32) * t).round() as u32;
-        0xFF000000 | (r << 16) | (g << 8) | b
+    fn dead_snake_render<R: PlatformRenderer>(&self, renderer: &R, player: usize) {
+        let Some(dead_snake) = &self.views[player].dead_snake else {
+            return;
+        };
+        for i in 1..dead_snake.items.len() {
+            self.fill_rect(
+                renderer,
+                dead_snake.items.get(i).unwrap(),
+                self.theme.players[player].body,
+            );
+            self.fill_fractured_spine(
+                renderer,
+                dead_snake.items.get(i).unwrap().into(),
+                *dead_snake.masks.get(i).unwrap(),
+            );
+        }
     }
 
-    fn snake_render(&self) {
-        let t = self.step_cooldown / STEP_INTERVAL;
+    fn snake_render<R: PlatformRenderer>(&self, renderer: &R, player: usize) {
+        let t = 1.0 - self.sim.step_alpha();
+        let view = &self.views[player];
+        let colors = &self.theme.players[player];
+        let snake = self.sim.player(player).snake();
 
-        let head_cell = self.snake.items.back().unwrap();
-        let head_dir = self.dir;
-        let mut head_slide_sides: Sides = (&Rect::from(head_cell)).into();
+        let head_cell = snake.head();
+        let head_dir = self.sim.player(player).dir();
+        let mut head_slide_sides: Sides = (&self.cell_rect(head_cell)).into();
         head_slide_sides.adjust_2_slide_sides(!head_dir, t);
 
-        let tail_cell = self.snake.items.front().unwrap();
-        let mut tail_slide_sides: Sides = (&Rect::from(tail_cell)).into();
-        let tail_dir = self
-            .snake
-            .items
-            .get(0)
-            .unwrap()
-            .determine_dir(self.snake.items.get(1).unwrap());
+        let tail_cell = snake.tail();
+        let mut tail_slide_sides: Sides = (&self.cell_rect(tail_cell)).into();
+        let tail_dir = tail_cell.determine_dir(snake.items.get(1).unwrap(), self.config());
         tail_slide_sides
-            .adjust_2_slide_sides(tail_dir, if self.eating_egg { 1.0 } else { 1.0 - t });
+            .adjust_2_slide_sides(tail_dir, if view.eating_egg { 1.0 } else { 1.0 - t });
 
-        if self.eating_egg {
-            // self.fill_cell(head_cell, EGG_BODY_COLOR, 1.0);
-            // self.fill_cell(
+        if view.eating_egg {
+            // self.fill_cell(renderer, head_cell, egg.body, 1.0);
+            // self.fill_cell(renderer,
             //     head_cell,
-            //     EGG_SPINE_COLOR,
+            //     egg.spine,
             //     SNAKE_SPINE_THICKNESS_PERCENT * 2.0,
             // );
-            let t = self.eating_timer;
-            let color = self.lerp_color(EGG_BODY_COLOR, SNAKE_HEAD_COLOR, t.sin()); // 动态颜色
-            self.fill_cell(head_cell, color, 1.0);
+            let t = view.eating_timer;
+            let body_color = self.theme.food(view.eaten_kind).body;
+            let color = body_color.lerp(colors.head, t.sin()); // 动态颜色
+            self.fill_cell(renderer, head_cell, color, 1.0);
         } else {
-            self.fill_sides(&head_slide_sides, SNAKE_HEAD_COLOR);
+            self.fill_sides(renderer, &head_slide_sides, colors.head);
         }
 
-        self.fill_sides(&tail_slide_sides, SNAKE_TAIL_COLOR);
+        self.fill_sides(renderer, &tail_slide_sides, colors.tail);
 
-        for i in 1..self.snake.size() - 1 {
-            let t = (i - 1) as f32 / (self.snake.size() - 2) as f32;
-            let color = self.lerp_color(self.body_start_color, self.body_end_color, t);
-            self.fill_cell(self.snake.items.get(i).unwrap(), color, 1.0);
+        for i in 1..snake.size() - 1 {
+            let t = (i - 1) as f32 / (snake.size() - 2) as f32;
+            let color = colors.body_start.lerp(colors.body_end, t);
+            self.fill_cell(renderer, snake.items.get(i).unwrap(), color, 1.0);
         }
 
         // body spine
-        for i in 1..self.snake.size() - 2 {
-            let cell1 = self.snake.items.get(i).unwrap();
-            let cell2 = self.snake.items.get(i + 1).unwrap();
-
-            self.fill_spine(cell1.center(), cell1.determine_dir(cell2), CELL_SIZE as f32);
-            self.fill_spine(cell2.center(), cell2.determine_dir(cell1), CELL_SIZE as f32);
+        for i in 1..snake.size() - 2 {
+            let cell1 = snake.items.get(i).unwrap();
+            let cell2 = snake.items.get(i + 1).unwrap();
+
+            self.fill_spine(
+                renderer,
+                self.cell_center(cell1),
+                cell1.determine_dir(cell2, self.config()),
+                self.cell_size(),
+            );
+            self.fill_spine(
+                renderer,
+                self.cell_center(cell2),
+                cell2.determine_dir(cell1, self.config()),
+                self.cell_size(),
+            );
         }
 
         // head spine
         {
-            let cell1 = self.snake.items.get(self.snake.size() - 2).unwrap();
-            let cell2 = self.snake.items.get(self.snake.size() - 1).unwrap();
-            let len = lerpf(0.0, CELL_SIZE as f32, 1.0 - t);
-            self.fill_spine(cell1.center(), cell1.determine_dir(cell2), len);
-            self.fill_spine((*cell2 + (!head_dir).into()).center(), head_dir, len);
+            let cell1 = snake.items.get(snake.size() - 2).unwrap();
+            let cell2 = snake.items.get(snake.size() - 1).unwrap();
+            let len = lerpf(0.0, self.cell_size(), 1.0 - t);
+            self.fill_spine(
+                renderer,
+                self.cell_center(cell1),
+                cell1.determine_dir(cell2, self.config()),
+                len,
+            );
+            self.fill_spine(
+                renderer,
+                self.cell_center(&(*cell2 + (!head_dir).into())),
+                head_dir,
+                len,
+            );
         }
 
         // tail spine
         {
-            let cell1 = self.snake.items.get(1).unwrap();
-            let cell2 = self.snake.items.get(0).unwrap();
-            let len = lerpf(0.0, CELL_SIZE as f32, if self.eating_egg { 0.0 } else { t });
-            self.fill_spine(cell1.center(), cell1.determine_dir(cell2), len);
-            self.fill_spine((*cell2 + tail_dir.into()).center(), !tail_dir, len);
+            let cell1 = snake.items.get(1).unwrap();
+            let cell2 = snake.tail();
+            let len = lerpf(0.0, self.cell_size(), if view.eating_egg { 0.0 } else { t });
+            self.fill_spine(
+                renderer,
+                self.cell_center(cell1),
+                cell1.determine_dir(cell2, self.config()),
+                len,
+            );
+            self.fill_spine(
+                renderer,
+                self.cell_center(&(*cell2 + tail_dir.into())),
+                !tail_dir,
+                len,
+            );
         }
 
         #[cfg(feature = "dev")]
         {
-            for i in 0..self.snake.size() {
-                self.stroke_rect(self.snake.items.get(i).unwrap().into(), 0xFF0000FF);
+            for i in 0..snake.size() {
+                self.stroke_rect(
+                    renderer,
+                    self.cell_rect(snake.items.get(i).unwrap()),
+                    Color::RED,
+                );
             }
         }
     }
 
-    fn stroke_rect(&self, rect: Rect, color: u32) {
-        self.platform_renderer.stroke_rect(
-            (rect.x - self.camera_pos.x) as i32 + (self.width / 2) as i32,
-            (rect.y - self.camera_pos.y) as i32 + (self.height / 2) as i32,
-            rect.w as i32,
-            rect.h as i32,
-            color,
-        );
+    #[cfg(feature = "dev")]
+    fn stroke_rect<R: PlatformRenderer>(&self, renderer: &R, rect: Rect, color: Color) {
+        self.screen_rects(&rect, |x, y, w, h| renderer.stroke_rect(x, y, w, h, color));
     }
 
-    fn fill_sides(&self, sides: &Sides, color: u32) {
-        self.fill_rect(&sides.into(), color);
+    fn fill_sides<R: PlatformRenderer>(&self, renderer: &R, sides: &Sides, color: Color) {
+        self.fill_rect(renderer, &sides.into(), color);
     }
 
-    fn random_egg(&mut self, first: bool) {
-        let (col1, col2, row1, row2) = (0, COLS - 1, 0, ROWS - 1);
-        let mut attempt = 0;
-        loop {
-            self.egg.x = (rand() % (col2 - col1 + 1) as u32) as i32 + col1;
-            self.egg.y = (rand() % (row2 - row1 + 1) as u32) as i32 + row1;
-            attempt += 1;
-
-            if !(self.snake.contains_cell(&self.egg) || (first && self.egg.y == SNAKE_INIT_ROW))
-                || attempt >= RANDOM_EGG_MAX_ATTEMPTS
+    fn foods_render<R: PlatformRenderer>(&self, renderer: &R) {
+        for food in self.sim.foods() {
+            if food
+                .ttl
+                .is_some_and(|ttl| ttl <= FOOD_BLINK_STEPS && ttl % 2 == 1)
             {
-                break;
+                continue;
             }
-        }
-        if attempt >= RANDOM_EGG_MAX_ATTEMPTS {
-            panic!("Max egg placement attempts reached");
-        }
-    }
 
-    fn egg_render(&self) {
-        if self.eating_egg {
-            let t = 1.0 - self.step_cooldown / STEP_INTERVAL;
-            let a = lerpf(1.5, 1.0, t * t);
-            self.fill_cell(&self.egg, self.color_alpha(EGG_BODY_COLOR, t * t), a);
-            self.fill_cell(
-                &self.egg,
-                self.color_alpha(EGG_SPINE_COLOR, t * t),
-                a * (SNAKE_SPINE_THICKNESS_PERCENT * 2.0),
-            );
-        } else {
-            self.fill_cell(&self.egg, EGG_BODY_COLOR, 1.0);
-            self.fill_cell(
-                &self.egg,
-                EGG_SPINE_COLOR,
-                SNAKE_SPINE_THICKNESS_PERCENT * 2.0,
-            );
+            let colors = self.theme.food(food.kind);
+            let (body_color, spine_color) = (colors.body, colors.spine);
+            if food.fresh {
+                let t = self.sim.step_alpha();
+                let a = lerpf(1.5, 1.0, t * t);
+                self.fill_cell(renderer, &food.cell, body_color.with_opacity(t * t), a);
+                self.fill_cell(
+                    renderer,
+                    &food.cell,
+                    spine_color.with_opacity(t * t),
+                    a * (SNAKE_SPINE_THICKNESS_PERCENT * 2.0),
+                );
+            } else {
+                self.fill_cell(renderer, &food.cell, body_color, 1.0);
+                self.fill_cell(
+                    renderer,
+                    &food.cell,
+                    spine_color,
+                    SNAKE_SPINE_THICKNESS_PERCENT * 2.0,
+                );
+            }
         }
     }
 
-    fn color_alpha(&self, color: u32, a: f32) -> u32 {
-        let rgb = color & 0x00FF_FFFF;
-        let alpha = ((a.clamp(0.0, 1.0) * 255.0).round() as u32) << 24;
-        rgb | alpha
-    }
+    /// Columns and rows of the cells the checkerboard is drawn for.
+    fn background_cells(&self) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
+        // A wrapping board is drawn as it is and tiled across the seam by
+        // `fill_rect`, so that the checkerboard lines up with its copies.
+        #[cfg(feature = "dynamic_camera")]
+        if self.config().edges == Edges::Wrap {
+            return (0..=self.config().cols - 1, 0..=self.config().rows - 1);
+        }
 
-    fn background_render(&self) {
-        let col1 = ((self.camera_pos.x - self.width as f32 * 0.5) as i32 - CELL_SIZE) / CELL_SIZE;
-        let col2 = ((self.camera_pos.x + self.width as f32 * 0.5) as i32 + CELL_SIZE) / CELL_SIZE;
+        let cell_size = self.config().cell_size;
+        let view = self.view_rect();
+        let col1 = (view.x as i32 - cell_size) / cell_size;
+        let col2 = ((view.x + view.w) as i32 + cell_size) / cell_size;
 
-        let row1 = ((self.camera_pos.y - self.height as f32 * 0.5) as i32 - CELL_SIZE) / CELL_SIZE;
-        let row2 = ((self.camera_pos.y + self.height as f32 * 0.5) as i32 + CELL_SIZE) / CELL_SIZE;
+        let row1 = (view.y as i32 - cell_size) / cell_size;
+        let row2 = ((view.y + view.h) as i32 + cell_size) / cell_size;
+        (col1..=col2, row1..=row2)
+    }
 
-        for col in col1..=col2 {
-            for row in row1..=row2 {
+    fn background_render<R: PlatformRenderer>(&self, renderer: &R) {
+        let (cols, rows) = self.background_cells();
+        for col in cols {
+            for row in rows.clone() {
                 let color = if (row + col) % 2 == 0 {
-                    CELL1_COLOR
+                    self.theme.background
                 } else {
-                    CELL2_COLOR
+                    self.theme.cell
                 };
                 let cell = Cell { x: col, y: row };
-                self.fill_cell(&cell, color, 1.0);
+                self.fill_cell(renderer, &cell, color, 1.0);
+            }
+        }
+
+        if let Some(level) = &self.config().level {
+            for wall in level.walls() {
+                self.fill_cell(renderer, &wall, self.theme.wall, 1.0);
+                self.fill_cell(renderer, &wall, self.theme.wall_inner, WALL_INNER_PERCENT);
             }
         }
     }
@@ -730,29 +1142,98 @@ impl<P: PlatformRenderer> Game<P> {
         r.y = lerpf(r.y, r.y + r.h * 0.5, 1.0 - a);
         r.w = lerpf(0.0, r.w, a);
         r.h = lerpf(0.0, r.h, a);
-        return r;
+        r
     }
 
-    fn fill_cell(&self, cell: &Cell, color: u32, a: f32) {
-        self.fill_rect(&self.scale_rect(cell.into(), a), color);
+    fn fill_cell<R: PlatformRenderer>(&self, renderer: &R, cell: &Cell, color: Color, a: f32) {
+        self.fill_rect(renderer, &self.scale_rect(self.cell_rect(cell), a), color);
     }
 
-    fn fill_rect(&self, rect: &Rect, color: u32) {
-        self.platform_renderer.fill_rect(
+    fn fill_rect<R: PlatformRenderer>(&self, renderer: &R, rect: &Rect, color: Color) {
+        self.screen_rects(rect, |x, y, w, h| renderer.fill_rect(x, y, w, h, color));
+    }
+
+    /// The part of the board on the screen.
+    #[cfg(not(feature = "dynamic_camera"))]
+    fn view_rect(&self) -> Rect {
+        Rect {
+            x: self.camera_pos.x - self.width as f32 * 0.5,
+            y: self.camera_pos.y - self.height as f32 * 0.5,
+            w: self.width as f32,
+            h: self.height as f32,
+        }
+    }
+
+    /// Calls `draw` with the screen rect of a rect of the board.
+    #[cfg(not(feature = "dynamic_camera"))]
+    fn screen_rects(&self, rect: &Rect, mut draw: impl FnMut(i32, i32, i32, i32)) {
+        draw(
             (rect.x - self.camera_pos.x + self.width as f32 / 2.0) as i32,
             (rect.y - self.camera_pos.y + self.height as f32 / 2.0) as i32,
             rect.w as i32,
             rect.h as i32,
-            color,
         );
     }
 
-    fn fill_text(&self, x: i32, y: i32, text: &str, size: u32, color: u32) {
-        self.platform_renderer.fill_text(x, y, text, size, color);
+    /// The part of the board on the screen.
+    #[cfg(feature = "dynamic_camera")]
+    fn view_rect(&self) -> Rect {
+        let (min, max) = self.camera.view();
+        Rect {
+            x: min.x,
+            y: min.y,
+            w: max.x - min.x,
+            h: max.y - min.y,
+        }
+    }
+
+    /// Calls `draw` with the screen rect of a rect of the board, once for
+    /// every copy of a wrapping board on the screen. Both corners are mapped
+    /// before rounding, so that rects sharing an edge still do when zoomed.
+    #[cfg(feature = "dynamic_camera")]
+    fn screen_rects(&self, rect: &Rect, mut draw: impl FnMut(i32, i32, i32, i32)) {
+        let min = Vec2 {
+            x: rect.x.min(rect.x + rect.w),
+            y: rect.y.min(rect.y + rect.h),
+        };
+        let max = Vec2 {
+            x: rect.x.max(rect.x + rect.w),
+            y: rect.y.max(rect.y + rect.h),
+        };
+        for offset in self.camera.tile_offsets(min, max) {
+            let top_left = self.camera.to_screen(min + offset);
+            let bottom_right = self.camera.to_screen(max + offset);
+            let x = top_left.x.floor() as i32;
+            let y = top_left.y.floor() as i32;
+            draw(
+                x,
+                y,
+                bottom_right.x.floor() as i32 - x,
+                bottom_right.y.floor() as i32 - y,
+            );
+        }
+    }
+
+    fn fill_text<R: PlatformRenderer>(
+        &self,
+        renderer: &R,
+        x: i32,
+        y: i32,
+        text: &str,
+        size: u32,
+        color: Color,
+    ) {
+        renderer.fill_text(x, y, text, size, color);
     }
 
-    fn fill_spine(&self, center: Vec2<f32>, dir: Direction, len: f32) {
-        let thicc = CELL_SIZE as f32 * SNAKE_SPINE_THICKNESS_PERCENT;
+    fn fill_spine<R: PlatformRenderer>(
+        &self,
+        renderer: &R,
+        center: Vec2<f32>,
+        dir: Direction,
+        len: f32,
+    ) {
+        let thicc = self.cell_size() * SNAKE_SPINE_THICKNESS_PERCENT;
         let mut sides = Sides {
             lens: vec![0.0; DIR_LENS],
         };
@@ -767,11 +1248,11 @@ impl<P: PlatformRenderer> Game<P> {
         if dir == Direction::Left || dir == Direction::Up {
             sides.lens[dir as usize] -= len;
         }
-        self.fill_sides(&sides, SNAKE_SPINE_COLOR);
+        self.fill_sides(renderer, &sides, self.theme.spine);
     }
 
-    fn fill_fractured_spine(&self, sides: Sides, mask: u8) {
-        let thicc = CELL_SIZE as f32 * SNAKE_SPINE_THICKNESS_PERCENT;
+    fn fill_fractured_spine<R: PlatformRenderer>(&self, renderer: &R, sides: Sides, mask: u8) {
+        let thicc = self.cell_size() * SNAKE_SPINE_THICKNESS_PERCENT;
         let center = sides.center();
         for dir in Direction::ALL {
             if (mask & (1 << dir as u8)) != 0 {
@@ -783,45 +1264,196 @@ impl<P: PlatformRenderer> Game<P> {
                 arm.lens[Direction::Up as usize] = center.y - thicc;
                 arm.lens[Direction::Down as usize] = center.y + thicc;
                 arm.lens[dir as usize] = sides.lens[dir as usize];
-                self.fill_sides(&arm, SNAKE_SPINE_COLOR);
+                self.fill_sides(renderer, &arm, self.theme.spine);
             }
         }
     }
 
-    fn init_dead_snake(&mut self, next_head: &Cell) {
-        let head_center = next_head.center();
-        self.dead_snake.reset();
+    fn init_dead_snake(&mut self, player: usize, next_head: &Cell) {
+        let head_center = self.cell_center(next_head);
+        let explosion_radius = GAMEOVER_EXPLOSION_RADIUS_CELLS * self.cell_size();
+        let explosion_max_vel = GAMEOVER_EXPLOSION_MAX_VEL_CELLS * self.cell_size();
+        let mut rng = self.rng;
+        let mut dead_snake = DeadSnake {
+            items: Vec::new(),
+            vels: Vec::new(),
+            masks: Vec::new(),
+        };
 
-        for (i, cell) in self.snake.items.iter().enumerate() {
-            self.dead_snake.items.push(cell.into());
+        let snake = self.sim.player(player).snake();
+        for (i, cell) in snake.items.iter().enumerate() {
+            dead_snake.items.push(self.cell_rect(cell));
 
             if *cell != *next_head {
-                let cell_center = cell.center();
+                let cell_center = self.cell_center(cell);
                 let vel_vec = cell_center - head_center;
                 let vel_len = (vel_vec.x.powi(2) + vel_vec.y.powi(2)).sqrt();
-                let t = (vel_len / GAMEOVER_EXPLOSION_RADIUS).clamp(0.0, 1.0);
+                let t = (vel_len / explosion_radius).clamp(0.0, 1.0);
                 let t = 1.0 - t;
-                let noise_x = (rand() % 1000) as f32 * 0.01;
-                let noise_y = (rand() % 1000) as f32 * 0.01;
-                let vel_x = (vel_vec.x / vel_len * GAMEOVER_EXPLOSION_MAX_VEL * t) + noise_x;
-                let vel_y = (vel_vec.y / vel_len * GAMEOVER_EXPLOSION_MAX_VEL * t) + noise_y;
-                self.dead_snake.vels.push(Vec2 { x: vel_x, y: vel_y });
+                let noise_x = (rng.next_u32() % 1000) as f32 * 0.01;
+                let noise_y = (rng.next_u32() % 1000) as f32 * 0.01;
+                let vel_x = (vel_vec.x / vel_len * explosion_max_vel * t) + noise_x;
+                let vel_y = (vel_vec.y / vel_len * explosion_max_vel * t) + noise_y;
+                dead_snake.vels.push(Vec2 { x: vel_x, y: vel_y });
             } else {
-                self.dead_snake.vels.push(Vec2 { x: 0.0, y: 0.0 }); // 头部不动
+                dead_snake.vels.push(Vec2 { x: 0.0, y: 0.0 }); // 头部不动
             }
 
             let mut mask = 0;
             if i > 0 {
-                let prev_cell = self.snake.items[i - 1];
-                let dir = cell.determine_dir(&prev_cell);
+                let prev_cell = snake.items[i - 1];
+                let dir = cell.determine_dir(&prev_cell, self.config());
                 mask |= 1 << dir as u8;
             }
-            if i < self.snake.items.len() - 1 {
-                let next_cell = self.snake.items[i + 1];
-                let dir = cell.determine_dir(&next_cell);
+            if i < snake.items.len() - 1 {
+                let next_cell = snake.items[i + 1];
+                let dir = cell.determine_dir(&next_cell, self.config());
                 mask |= 1 << dir as u8;
             }
-            self.dead_snake.masks.push(mask);
+            dead_snake.masks.push(mask);
+        }
+
+        self.views[player].dead_snake = Some(dead_snake);
+        self.rng = rng;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{render::RecordingRenderer, settings::MemorySettings};
+
+    fn sides(x: f32, y: f32, w: f32, h: f32) -> Sides {
+        (&Rect { x, y, w, h }).into()
+    }
+
+    fn rect(sides: &Sides) -> (f32, f32, f32, f32) {
+        let rect: Rect = sides.into();
+        (rect.x, rect.y, rect.w, rect.h)
+    }
+
+    #[test]
+    fn slide_keeps_size_and_moves_towards_dir() {
+        for (dir, t, expected) in [
+            (Direction::Right, 0.0, (10.0, 20.0, 10.0, 10.0)),
+            (Direction::Right, 0.5, (15.0, 20.0, 10.0, 10.0)),
+            (Direction::Right, 1.0, (20.0, 20.0, 10.0, 10.0)),
+            (Direction::Left, 0.25, (7.5, 20.0, 10.0, 10.0)),
+            (Direction::Down, 0.5, (10.0, 25.0, 10.0, 10.0)),
+            (Direction::Up, 1.0, (10.0, 10.0, 10.0, 10.0)),
+        ] {
+            let mut s = sides(10.0, 20.0, 10.0, 10.0);
+            s.adjust_2_slide_sides(dir, t);
+            assert_eq!(rect(&s), expected, "{:?} at {}", dir, t);
+        }
+    }
+
+    #[test]
+    fn slide_back_undoes_slide() {
+        let mut s = sides(10.0, 20.0, 10.0, 10.0);
+        s.adjust_2_slide_sides(Direction::Up, 0.3);
+        s.adjust_2_slide_sides(Direction::Down, 0.3);
+        let (x, y, w, h) = rect(&s);
+        assert!((x - 10.0).abs() < 1e-4 && (y - 20.0).abs() < 1e-4);
+        assert!((w - 10.0).abs() < 1e-4 && (h - 10.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn center_of_sides() {
+        let center = sides(10.0, 20.0, 10.0, 30.0).center();
+        assert_eq!((center.x, center.y), (15.0, 35.0));
+    }
+
+    #[test]
+    fn update_catches_up_on_a_few_steps() {
+        let mut game = Game::new(RecordingRenderer::new(), GameConfig::default(), 1);
+        game.update(0.0);
+        assert_eq!(game.simulation().tick(), 1);
+
+        let interval = game.simulation().step_interval();
+        game.update(interval * 2.5);
+        assert_eq!(game.simulation().tick(), 3);
+        assert!((game.simulation().step_alpha() - 0.5).abs() < 1e-3);
+
+        // A long hitch runs a few steps and skips the rest, keeping the phase.
+        game.update(interval * 100.0);
+        assert_eq!(game.simulation().tick(), 3 + MAX_STEPS_PER_UPDATE as u64);
+        assert!((game.simulation().step_alpha() - 0.5).abs() < 1e-3);
+        assert!(!game.simulation().step_due());
+    }
+
+    #[test]
+    fn events_are_kept_once_asked_for() {
+        let config = GameConfig {
+            edges: crate::config::Edges::Solid,
+            ..GameConfig::default()
+        };
+        let mut game = Game::new(RecordingRenderer::new(), config, 1);
+        game.pause();
+        assert_eq!(game.take_events(), []);
+
+        game.record_events();
+        game.action(Action::Pause);
+        game.restart_with_seed(5);
+        assert_eq!(
+            game.take_events(),
+            [GameEvent::Resumed, GameEvent::Started(5)]
+        );
+        assert_eq!(game.take_events(), []);
+
+        // Straight into the edge.
+        for _ in 0..game.config().cols {
+            game.update(game.simulation().step_interval());
+        }
+        assert_eq!(game.simulation().state(), State::GameOver);
+        let events = game.take_events();
+        assert_eq!(
+            events[events.len() - 2..],
+            [GameEvent::Died(0), GameEvent::Over(None)]
+        );
+    }
+
+    #[test]
+    fn players_past_the_last_slot_are_ignored() {
+        let mut game = Game::new(RecordingRenderer::new(), GameConfig::default(), 1);
+        game.set_player_name(MAX_PLAYERS, "Nobody");
+        game.set_controller(
+            MAX_PLAYERS,
+            Some(crate::controller::Strategy::Bfs.controller()),
+        );
+        assert_eq!(
+            game.player_name(MAX_PLAYERS),
+            format!("Player {}", MAX_PLAYERS + 1)
+        );
+
+        game.set_player_name(0, " Ada\n");
+        assert_eq!(game.player_name(0), "Ada");
+    }
+
+    #[test]
+    fn resume_picks_up_the_saved_round_paused() {
+        let mut game = Game::new(RecordingRenderer::new(), GameConfig::default(), 1);
+        for _ in 0..12 {
+            game.update(game.simulation().step_interval());
+        }
+        let mut settings = MemorySettings::default();
+        settings.save(SAVE_SETTING, &game.save());
+
+        let mut resumed = Game::new(RecordingRenderer::new(), GameConfig::default(), 2);
+        resumed.set_settings_store(Box::new(settings));
+        assert!(resumed.can_resume());
+        assert!(resumed.resume());
+        assert_eq!(resumed.simulation().state(), State::Pause);
+        assert_eq!(resumed.simulation().tick(), game.simulation().tick());
+        assert_eq!(resumed.simulation().seed(), game.simulation().seed());
+
+        assert_eq!(resumed.replay(), None);
+        resumed.restart();
+        for _ in 0..5 {
+            resumed.update(resumed.simulation().step_interval());
         }
+        let summary = resumed.replay().unwrap().verify().unwrap();
+        assert_eq!(summary.seed, resumed.simulation().seed());
+        assert_eq!(summary.tick, resumed.simulation().tick());
     }
 }
@@ -0,0 +1,201 @@
+//! Gamepads, read once a frame. Buttons and axes follow the browser's
+//! standard mapping, an Xbox style layout.
+
+use crate::{keymap::Action, simulation::Direction};
+
+// Constants
+const BUTTON_A: usize = 0;
+const BUTTON_Y: usize = 3;
+const BUTTON_SELECT: usize = 8;
+const BUTTON_START: usize = 9;
+const BUTTON_DPAD: [(usize, Direction); 4] = [
+    (12, Direction::Up),
+    (13, Direction::Down),
+    (14, Direction::Left),
+    (15, Direction::Right),
+];
+const AXIS_LEFT_X: usize = 0;
+const AXIS_LEFT_Y: usize = 1;
+/// How far the stick has to be pushed to turn.
+const STICK_PRESS: f32 = 0.5;
+/// How far back it has to come before it lets go of a direction, lower than
+/// `STICK_PRESS` so a stick resting near it does not turn over and over.
+const STICK_RELEASE: f32 = 0.3;
+
+/// What a pad reports in one frame.
+#[derive(Clone, Default, Debug)]
+pub struct PadState {
+    pub buttons: Vec<bool>,
+    /// From -1 to 1, with y going down.
+    pub axes: Vec<f32>,
+}
+
+impl PadState {
+    fn button(&self, button: usize) -> bool {
+        self.buttons.get(button).copied().unwrap_or(false)
+    }
+
+    fn axis(&self, axis: usize) -> f32 {
+        self.axes.get(axis).copied().unwrap_or(0.0)
+    }
+}
+
+/// How far the stick is pushed towards `dir`.
+fn stick_along(x: f32, y: f32, dir: Direction) -> f32 {
+    match dir {
+        Direction::Right => x,
+        Direction::Left => -x,
+        Direction::Down => y,
+        Direction::Up => -y,
+    }
+}
+
+/// The direction the stick points to, keeping `held` until the stick comes
+/// back past `STICK_RELEASE` or is pushed the other way.
+fn stick_direction(x: f32, y: f32, held: Option<Direction>) -> Option<Direction> {
+    let dominant = if x.abs() >= y.abs() {
+        if x > 0.0 {
+            Direction::Right
+        } else {
+            Direction::Left
+        }
+    } else if y > 0.0 {
+        Direction::Down
+    } else {
+        Direction::Up
+    };
+    let pushed = x.abs().max(y.abs()) >= STICK_PRESS;
+    match held {
+        Some(dir) if stick_along(x, y, dir) >= STICK_RELEASE && !(pushed && dominant != dir) => {
+            Some(dir)
+        }
+        _ if pushed => Some(dominant),
+        _ => None,
+    }
+}
+
+/// One pad and the player slot it steers.
+struct PadReader {
+    slot: usize,
+    buttons: Vec<bool>,
+    stick: Option<Direction>,
+}
+
+impl PadReader {
+    fn new(slot: usize) -> Self {
+        Self {
+            slot,
+            buttons: Vec::new(),
+            stick: None,
+        }
+    }
+
+    fn poll(&mut self, state: &PadState) -> Vec<Action> {
+        let pressed =
+            |button: usize| state.button(button) && !self.buttons.get(button).is_some_and(|b| *b);
+        let mut actions = Vec::new();
+        for (button, dir) in BUTTON_DPAD {
+            if pressed(button) {
+                actions.push(Action::Turn(self.slot, dir));
+            }
+        }
+        if pressed(BUTTON_START) || pressed(BUTTON_A) {
+            actions.push(Action::Pause);
+        }
+        if pressed(BUTTON_SELECT) || pressed(BUTTON_Y) {
+            actions.push(Action::Restart);
+        }
+
+        let stick = stick_direction(state.axis(AXIS_LEFT_X), state.axis(AXIS_LEFT_Y), self.stick);
+        if let Some(dir) = stick
+            && stick != self.stick
+        {
+            actions.push(Action::Turn(self.slot, dir));
+        }
+
+        self.stick = stick;
+        self.buttons.clone_from(&state.buttons);
+        actions
+    }
+}
+
+/// Every pad seen so far, by the index the browser gives it.
+#[derive(Default)]
+pub struct Gamepads {
+    readers: Vec<PadReader>,
+}
+
+impl Gamepads {
+    pub const fn new() -> Self {
+        Self {
+            readers: Vec::new(),
+        }
+    }
+
+    fn reader(&mut self, pad: usize) -> &mut PadReader {
+        while self.readers.len() <= pad {
+            let slot = self.readers.len();
+            self.readers.push(PadReader::new(slot));
+        }
+        &mut self.readers[pad]
+    }
+
+    /// Lets `pad` steer the snake of player slot `slot`. Pad n steers player
+    /// slot n until told otherwise.
+    pub fn assign(&mut self, pad: usize, slot: usize) {
+        self.reader(pad).slot = slot;
+    }
+
+    /// Actions for what changed on `pad` since it was last polled.
+    pub fn poll(&mut self, pad: usize, state: &PadState) -> Vec<Action> {
+        self.reader(pad).poll(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stick(x: f32, y: f32) -> PadState {
+        PadState {
+            buttons: Vec::new(),
+            axes: vec![x, y],
+        }
+    }
+
+    #[test]
+    fn buttons_act_when_pressed() {
+        let mut pads = Gamepads::new();
+        pads.assign(1, 2);
+        let mut state = PadState {
+            buttons: vec![false; 16],
+            axes: Vec::new(),
+        };
+        state.buttons[14] = true;
+        state.buttons[BUTTON_START] = true;
+        let left = Action::Turn(2, Direction::Left);
+        assert_eq!(pads.poll(1, &state), [left, Action::Pause]);
+        assert_eq!(pads.poll(1, &state), []);
+        let left = Action::Turn(0, Direction::Left);
+        assert_eq!(pads.poll(0, &state), [left, Action::Pause]);
+        state.buttons[BUTTON_START] = false;
+        state.buttons[BUTTON_SELECT] = true;
+        assert_eq!(pads.poll(1, &state), [Action::Restart]);
+    }
+
+    #[test]
+    fn stick_has_deadzone_and_hysteresis() {
+        let mut pads = Gamepads::new();
+        assert_eq!(pads.poll(2, &stick(0.4, 0.1)), []);
+        let right = Action::Turn(2, Direction::Right);
+        assert_eq!(pads.poll(2, &stick(0.6, 0.1)), [right]);
+        // Wobbling around the press threshold keeps the direction.
+        assert_eq!(pads.poll(2, &stick(0.45, 0.1)), []);
+        assert_eq!(pads.poll(2, &stick(0.55, 0.1)), []);
+        // Pushed the other way it turns right away.
+        let down = Action::Turn(2, Direction::Down);
+        assert_eq!(pads.poll(2, &stick(0.35, 0.7)), [down]);
+        assert_eq!(pads.poll(2, &stick(0.0, 0.2)), []);
+        assert_eq!(pads.poll(2, &stick(0.0, 0.6)), [down]);
+    }
+}
@@ -0,0 +1,418 @@
+//! A game mounted on a canvas, and the handle the host page controls it with.
+//!
+//! ```js
+//! const snake = new SnakeHandle(canvas, '{"cols": 24, "rows": 12}');
+//! snake.on_event((event) => console.log(event.type, event));
+//! snake.restart(42n);
+//! ```
+
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+use wasm_bindgen::prelude::*;
+use web_sys::{HtmlCanvasElement, KeyboardEvent, PointerEvent, js_sys};
+
+use crate::{
+    config::GameConfig,
+    document,
+    game::{Game, GameEvent},
+    gamepad::{Gamepads, PadState},
+    random_seed,
+    render::WebPlatformRenderer,
+    save::{SaveError, state_name},
+    simulation::{Input, State, Vec2},
+    touch::SwipeDetector,
+    window,
+};
+
+/// A game and what the page feeds it.
+pub(crate) struct Mount {
+    pub(crate) game: Game<WebPlatformRenderer>,
+    canvas: HtmlCanvasElement,
+    /// `None` until the first frame, which has nothing to measure from.
+    prev_timestamp: Option<f32>,
+    swipes: SwipeDetector,
+    pub(crate) gamepads: Gamepads,
+    /// Called with every event of the game, see [`SnakeHandle::on_event`].
+    listeners: Vec<js_sys::Function>,
+    /// Listeners on the page, removed again when the mount is dropped.
+    dom_listeners: Vec<DomListener>,
+}
+
+impl Drop for Mount {
+    fn drop(&mut self) {
+        for listener in &self.dom_listeners {
+            let _ = listener.target.remove_event_listener_with_callback(
+                listener.event,
+                listener.closure.as_ref().unchecked_ref(),
+            );
+        }
+    }
+}
+
+/// A closure listening to `event` on `target`.
+struct DomListener {
+    target: web_sys::EventTarget,
+    event: &'static str,
+    closure: Closure<dyn FnMut(web_sys::Event)>,
+}
+
+/// Starts a game on `canvas`, resized to fit the board, that runs until the
+/// returned mount is dropped.
+pub(crate) fn mount(
+    canvas: HtmlCanvasElement,
+    config: GameConfig,
+    seed: u64,
+) -> Result<Rc<RefCell<Mount>>, JsValue> {
+    let ctx = canvas
+        .get_context("2d")?
+        .ok_or_else(|| JsValue::from_str("canvas has no 2d context"))?
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
+    canvas.set_width(config.width());
+    canvas.set_height(config.height());
+
+    let mut game = Game::new(WebPlatformRenderer::new(ctx), config, seed);
+    game.record_events();
+    let mount = Rc::new(RefCell::new(Mount {
+        game,
+        canvas: canvas.clone(),
+        prev_timestamp: None,
+        swipes: SwipeDetector::new(),
+        gamepads: Gamepads::new(),
+        listeners: Vec::new(),
+        dom_listeners: Vec::new(),
+    }));
+
+    let weak = Rc::downgrade(&mount);
+    let mut dom_listeners = vec![listen(
+        &document(),
+        "keydown",
+        &weak,
+        |mount, e: KeyboardEvent| {
+            mount.game.keydown(&e.code(), &e.key());
+        },
+    )];
+    dom_listeners.push(listen(
+        &document(),
+        "visibilitychange",
+        &weak,
+        |mount, _: web_sys::Event| {
+            if document().hidden() {
+                mount.game.pause();
+                mount.game.autosave();
+            }
+            // No frames come while the page is hidden, so time the next one afresh.
+            mount.prev_timestamp = None;
+        },
+    ));
+    // Mostly covered by `visibilitychange`, which some browsers skip on the way out.
+    dom_listeners.push(listen(
+        &window(),
+        "beforeunload",
+        &weak,
+        |mount, _: web_sys::Event| {
+            mount.game.autosave();
+        },
+    ));
+    dom_listeners.extend(add_pointer_listeners(&canvas, &weak));
+    mount.borrow_mut().dom_listeners = dom_listeners;
+
+    request_frame(weak);
+    Ok(mount)
+}
+
+/// Calls `f` with the mount on every `event` of `target`, until the returned
+/// listener is removed with the mount.
+fn listen<E: JsCast + 'static>(
+    target: &web_sys::EventTarget,
+    event: &'static str,
+    mount: &Weak<RefCell<Mount>>,
+    mut f: impl FnMut(&mut Mount, E) + 'static,
+) -> DomListener {
+    let mount = mount.clone();
+    let listener = Closure::wrap(Box::new(move |e: web_sys::Event| {
+        if let Some(mount) = mount.upgrade() {
+            f(&mut mount.borrow_mut(), e.unchecked_into());
+        }
+    }) as Box<dyn FnMut(_)>);
+
+    target
+        .add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())
+        .unwrap();
+
+    DomListener {
+        target: target.clone(),
+        event,
+        closure: listener,
+    }
+}
+
+/// Swipes and taps on the canvas, for phones and tablets.
+fn add_pointer_listeners(
+    canvas: &HtmlCanvasElement,
+    mount: &Weak<RefCell<Mount>>,
+) -> Vec<DomListener> {
+    let mut listeners = Vec::new();
+    for event in ["pointerdown", "pointermove", "pointerup", "pointercancel"] {
+        let target = canvas.clone();
+        let listener = listen(canvas, event, mount, move |mount, e: PointerEvent| {
+            // A mouse clicks to focus the page rather than to play.
+            if !e.is_primary() || e.pointer_type() == "mouse" {
+                return;
+            }
+            let pos = Vec2 {
+                x: e.client_x() as f32,
+                y: e.client_y() as f32,
+            };
+            let time = (e.time_stamp() / 1000.0) as f32;
+            let gesture = match e.type_().as_str() {
+                "pointerdown" => {
+                    // Keep getting moves when the finger leaves the canvas.
+                    let _ = target.set_pointer_capture(e.pointer_id());
+                    mount.swipes.pointer_down(pos, time);
+                    None
+                }
+                "pointermove" => mount.swipes.pointer_move(pos),
+                "pointerup" => mount.swipes.pointer_up(pos, time),
+                _ => {
+                    mount.swipes.cancel();
+                    None
+                }
+            };
+            if let Some(gesture) = gesture {
+                mount.game.gesture(gesture);
+            }
+        });
+        listeners.push(listener);
+    }
+    listeners
+}
+
+fn request_frame(mount: Weak<RefCell<Mount>>) {
+    window()
+        .request_animation_frame(
+            Closure::once_into_js(move |timestamp: f32| frame(mount, timestamp)).unchecked_ref(),
+        )
+        .unwrap();
+}
+
+fn frame(mount: Weak<RefCell<Mount>>, timestamp: f32) {
+    // The handle is gone, and the game with it.
+    let Some(strong) = mount.upgrade() else {
+        return;
+    };
+    let (events, listeners) = {
+        let mut mount = strong.borrow_mut();
+        let dt = mount
+            .prev_timestamp
+            .map_or(0.0, |prev| (timestamp - prev) / 1000.0);
+        mount.prev_timestamp = Some(timestamp);
+
+        poll_gamepads(&mut mount);
+        mount.game.update(dt);
+        mount.game.render();
+        (mount.game.take_events(), mount.listeners.clone())
+    };
+
+    // Out of the borrow, so the listeners can call back into the handle.
+    for event in events {
+        let event = event_object(event);
+        for listener in &listeners {
+            if let Err(err) = listener.call1(&JsValue::NULL, &event) {
+                web_sys::console::error_1(&err);
+            }
+        }
+    }
+
+    request_frame(mount);
+}
+
+/// Feeds what changed on the connected gamepads since the last frame to the game.
+fn poll_gamepads(mount: &mut Mount) {
+    let Ok(pads) = window().navigator().get_gamepads() else {
+        return;
+    };
+    // Slots of disconnected pads are null.
+    for pad in pads
+        .iter()
+        .filter_map(|pad| pad.dyn_into::<web_sys::Gamepad>().ok())
+    {
+        if !pad.connected() {
+            continue;
+        }
+        let state = PadState {
+            buttons: pad
+                .buttons()
+                .iter()
+                .map(|button| {
+                    button
+                        .dyn_into::<web_sys::GamepadButton>()
+                        .is_ok_and(|button| button.pressed())
+                })
+                .collect(),
+            axes: pad
+                .axes()
+                .iter()
+                .map(|axis| axis.as_f64().unwrap_or(0.0) as f32)
+                .collect(),
+        };
+        for action in mount.gamepads.poll(pad.index() as usize, &state) {
+            mount.game.press(Some(action));
+        }
+    }
+}
+
+/// An event as a plain object with a `type`, players counting from 1.
+fn event_object(event: GameEvent) -> JsValue {
+    let object = js_sys::Object::new();
+    let set = |name: &str, value: JsValue| {
+        js_sys::Reflect::set(&object, &JsValue::from_str(name), &value).unwrap();
+    };
+    let player = |player: usize| JsValue::from(player + 1);
+    match event {
+        GameEvent::Started(seed) => {
+            set("type", "started".into());
+            set("seed", js_sys::BigInt::from(seed).into());
+        }
+        GameEvent::Paused => set("type", "paused".into()),
+        GameEvent::Resumed => set("type", "resumed".into()),
+        GameEvent::Ate(eater, food) => {
+            set("type", "ate".into());
+            set("player", player(eater));
+            set("food", food.name().into());
+        }
+        GameEvent::Died(dead) => {
+            set("type", "died".into());
+            set("player", player(dead));
+        }
+        GameEvent::Over(winner) => {
+            set("type", "over".into());
+            set("winner", winner.map_or(JsValue::NULL, player));
+        }
+    }
+    object.into()
+}
+
+/// The board settings in `json`, an object with any of the query parameters
+/// of the page: `cols`, `rows`, `cell`, `edges`, `players`, `difficulty` and
+/// `level`, over `config`. Other keys, and values that are neither strings
+/// nor numbers, are errors.
+fn config_from_json(config: GameConfig, json: &str) -> Result<GameConfig, JsValue> {
+    let object = js_sys::JSON::parse(json)?;
+    if !object.is_object() {
+        return Err(JsValue::from_str("config is not an object"));
+    }
+    for key in js_sys::Object::keys(object.unchecked_ref()).iter() {
+        let key = key.as_string().unwrap_or_default();
+        if !GameConfig::PARAMS.contains(&key.as_str()) {
+            return Err(JsValue::from_str(&format!("unknown config key `{}`", key)));
+        }
+    }
+    config
+        .with_params(|name| {
+            let value = js_sys::Reflect::get(&object, &JsValue::from_str(name)).ok()?;
+            if value.is_undefined() {
+                return None;
+            }
+            // Anything else goes through as JSON, to be reported as invalid.
+            value
+                .as_string()
+                .or_else(|| value.as_f64().map(|n| n.to_string()))
+                .or_else(|| js_sys::JSON::stringify(&value).ok().map(String::from))
+        })
+        .map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+/// Controls one game from the page. Dropping the handle with `free()` stops
+/// the game.
+#[wasm_bindgen]
+pub struct SnakeHandle {
+    mount: Rc<RefCell<Mount>>,
+}
+
+impl SnakeHandle {
+    pub(crate) fn from_mount(mount: Rc<RefCell<Mount>>) -> Self {
+        Self { mount }
+    }
+}
+
+#[wasm_bindgen]
+impl SnakeHandle {
+    /// Starts a game on `canvas`, with board settings in JSON as for
+    /// [`SnakeHandle::set_config`]. Keys go to every game on the page.
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas: HtmlCanvasElement, config: Option<String>) -> Result<SnakeHandle, JsValue> {
+        let config = match config {
+            Some(json) => config_from_json(GameConfig::default(), &json)?,
+            None => GameConfig::default(),
+        };
+        Ok(Self::from_mount(mount(canvas, config, random_seed())?))
+    }
+
+    /// Pauses a round in play.
+    pub fn pause(&self) {
+        self.mount.borrow_mut().game.pause();
+    }
+
+    /// Carries on with a paused round.
+    pub fn resume(&self) {
+        let game = &mut self.mount.borrow_mut().game;
+        if game.simulation().state() == State::Pause {
+            game.apply_input(Input::TogglePause);
+        }
+    }
+
+    /// Starts a new round, from `seed` if given.
+    pub fn restart(&self, seed: Option<u64>) {
+        let game = &mut self.mount.borrow_mut().game;
+        match seed {
+            Some(seed) => game.restart_with_seed(seed),
+            None => game.restart(),
+        }
+    }
+
+    /// The highest score among the players.
+    pub fn score(&self) -> u32 {
+        self.mount.borrow().game.simulation().score()
+    }
+
+    /// `play`, `pause` or `over`.
+    pub fn state(&self) -> String {
+        state_name(self.mount.borrow().game.simulation().state()).to_string()
+    }
+
+    /// Changes the board and starts a new round on it. `json` holds any of
+    /// the query parameters of the page, e.g. `{"cols": 24, "edges": "solid"}`.
+    pub fn set_config(&self, json: &str) -> Result<(), JsValue> {
+        let mount = &mut *self.mount.borrow_mut();
+        let config = config_from_json(mount.game.config().clone(), json)?;
+        mount.canvas.set_width(config.width());
+        mount.canvas.set_height(config.height());
+        mount.game.restart_with_config(config, random_seed());
+        Ok(())
+    }
+
+    /// Calls `callback` with every event of the game, once a frame: objects
+    /// with a `type` of `started` (with the `seed`), `paused`, `resumed`,
+    /// `ate` (with the `player` and the `food`), `died` (with the `player`) or
+    /// `over` (with the `winner`, or null).
+    pub fn on_event(&self, callback: js_sys::Function) {
+        self.mount.borrow_mut().listeners.push(callback);
+    }
+
+    /// The round in progress in the saved game text format.
+    pub fn snapshot(&self) -> String {
+        self.mount.borrow().game.save()
+    }
+
+    /// Carries on with a round from [`SnakeHandle::snapshot`] of the same board.
+    pub fn restore(&self, snapshot: &str) -> Result<(), JsValue> {
+        self.mount
+            .borrow_mut()
+            .game
+            .restore(snapshot)
+            .map_err(|err: SaveError| JsValue::from_str(&err.to_string()))
+    }
+}
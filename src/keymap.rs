@@ -0,0 +1,287 @@
+//! Keyboard bindings and their text format.
+//!
+//! A binding is compared with both `KeyboardEvent.code`, the physical key
+//! (`KeyW`, `Space`, `ArrowUp`), and `KeyboardEvent.key`, what it types
+//! (`w`, `z`, `+`). Codes keep WASD in place on any layout, keys follow the
+//! letters printed on it. A keymap is written one action per line, followed
+//! by its bindings:
+//!
+//! ```text
+//! player1.up KeyW ArrowUp
+//! pause Space p
+//! ```
+//!
+//! Lines starting with `#` are comments. Actions that are left out keep their
+//! default bindings, and an action without bindings is unbound.
+
+use std::fmt;
+
+use crate::simulation::Direction;
+
+pub const KEYMAP_PLAYERS: usize = 2;
+
+const COMMENT: char = '#';
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    /// Turns the snake of a player slot.
+    Turn(usize, Direction),
+    Pause,
+    Restart,
+    NextTheme,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    DevSlower,
+    DevFaster,
+    DevNormalSpeed,
+    DevLogReplay,
+}
+
+impl Action {
+    /// Actions besides turning, in the order they are written.
+    pub const OTHERS: [Action; 10] = [
+        Action::Pause,
+        Action::Restart,
+        Action::NextTheme,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ZoomReset,
+        Action::DevSlower,
+        Action::DevFaster,
+        Action::DevNormalSpeed,
+        Action::DevLogReplay,
+    ];
+
+    /// Whether the action plays the game, as opposed to changing how it looks or runs.
+    pub fn is_gameplay(self) -> bool {
+        matches!(self, Action::Turn(..) | Action::Pause | Action::Restart)
+    }
+
+    fn all() -> Vec<Action> {
+        let mut actions = Vec::new();
+        for player in 0..KEYMAP_PLAYERS {
+            for dir in [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ] {
+                actions.push(Action::Turn(player, dir));
+            }
+        }
+        actions.extend(Action::OTHERS);
+        actions
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Action::Turn(player, dir) => {
+                let dir = match dir {
+                    Direction::Up => "up",
+                    Direction::Down => "down",
+                    Direction::Left => "left",
+                    Direction::Right => "right",
+                };
+                return write!(f, "player{}.{}", player + 1, dir);
+            }
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+            Action::NextTheme => "theme",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::ZoomReset => "zoom_reset",
+            Action::DevSlower => "dev.slower",
+            Action::DevFaster => "dev.faster",
+            Action::DevNormalSpeed => "dev.normal_speed",
+            Action::DevLogReplay => "dev.log_replay",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for Action {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::all()
+            .into_iter()
+            .find(|action| action.to_string() == s)
+            .ok_or(())
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct KeymapError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+/// The keys bound to every action.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<String>)>,
+}
+
+impl Keymap {
+    /// The bindings of `action`, codes and keys alike.
+    pub fn bindings(&self, action: Action) -> &[String] {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map_or(&[], |(_, bindings)| bindings)
+    }
+
+    pub fn set_bindings(&mut self, action: Action, bindings: Vec<String>) {
+        match self.bindings.iter_mut().find(|(a, _)| *a == action) {
+            Some((_, old)) => *old = bindings,
+            None => self.bindings.push((action, bindings)),
+        }
+    }
+
+    /// The action for a key press. A binding to the physical key wins over
+    /// one to the typed character, so `KeyZ` and `z` can mean different things.
+    pub fn action(&self, code: &str, key: &str) -> Option<Action> {
+        let find = |name: &str| {
+            self.bindings
+                .iter()
+                .find(|(_, bindings)| bindings.iter().any(|b| b == name))
+                .map(|(action, _)| *action)
+        };
+        find(code).or_else(|| find(key))
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        let turns = [
+            [
+                (Direction::Up, ["KeyW"]),
+                (Direction::Down, ["KeyS"]),
+                (Direction::Left, ["KeyA"]),
+                (Direction::Right, ["KeyD"]),
+            ],
+            [
+                (Direction::Up, ["ArrowUp"]),
+                (Direction::Down, ["ArrowDown"]),
+                (Direction::Left, ["ArrowLeft"]),
+                (Direction::Right, ["ArrowRight"]),
+            ],
+        ];
+        let mut keymap = Keymap {
+            bindings: Vec::new(),
+        };
+        for (player, keys) in turns.iter().enumerate() {
+            for (dir, names) in keys {
+                keymap.set_bindings(Action::Turn(player, *dir), bindings(names));
+            }
+        }
+        for (action, names) in [
+            (Action::Pause, &["Space"][..]),
+            (Action::Restart, &["KeyR"]),
+            (Action::NextTheme, &["KeyT"]),
+            (Action::ZoomIn, &["Equal", "NumpadAdd", "+"]),
+            (Action::ZoomOut, &["Minus", "NumpadSubtract"]),
+            (Action::ZoomReset, &["Digit0", "Numpad0"]),
+            (Action::DevSlower, &["KeyZ"]),
+            (Action::DevFaster, &["KeyX"]),
+            (Action::DevNormalSpeed, &["KeyC"]),
+            (Action::DevLogReplay, &["KeyV"]),
+        ] {
+            keymap.set_bindings(action, bindings(names));
+        }
+        keymap
+    }
+}
+
+impl std::str::FromStr for Keymap {
+    type Err = KeymapError;
+
+    /// Starts from the default keymap and rebinds the actions that are listed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |line: usize, message: String| KeymapError { line, message };
+
+        let mut keymap = Keymap::default();
+        let mut seen = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(COMMENT) {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let name = words.next().unwrap();
+            let action = name
+                .parse::<Action>()
+                .map_err(|_| error(i + 1, format!("unknown action `{}`", name)))?;
+            if seen.contains(&action) {
+                return Err(error(i + 1, format!("`{}` is given twice", name)));
+            }
+            keymap.set_bindings(action, words.map(str::to_string).collect());
+            seen.push(action);
+        }
+        Ok(keymap)
+    }
+}
+
+impl fmt::Display for Keymap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (action, bindings) in &self.bindings {
+            write!(f, "{}", action)?;
+            for binding in bindings {
+                write!(f, " {}", binding)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_win_over_keys() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action("KeyW", "z"),
+            Some(Action::Turn(0, Direction::Up))
+        );
+        assert_eq!(keymap.action("Equal", "+"), Some(Action::ZoomIn));
+        assert_eq!(keymap.action("BracketRight", "+"), Some(Action::ZoomIn));
+        assert_eq!(keymap.action("KeyQ", "q"), None);
+    }
+
+    #[test]
+    fn text_rebinds_listed_actions() {
+        let keymap: Keymap = "# comment\nplayer1.up KeyW ArrowUp\npause\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            keymap.bindings(Action::Turn(0, Direction::Up)),
+            ["KeyW", "ArrowUp"]
+        );
+        assert!(keymap.bindings(Action::Pause).is_empty());
+        assert_eq!(keymap.bindings(Action::Restart), ["KeyR"]);
+        assert_eq!(keymap.to_string().parse(), Ok(keymap));
+
+        let err = "pause Space\njump KeyJ\n".parse::<Keymap>().unwrap_err();
+        assert_eq!(
+            (err.line, err.message.as_str()),
+            (2, "unknown action `jump`")
+        );
+        let err = "pause Space\npause KeyP\n".parse::<Keymap>().unwrap_err();
+        assert_eq!(err.message, "`pause` is given twice");
+    }
+}
@@ -0,0 +1,250 @@
+//! Text level format.
+//!
+//! A level is an ASCII grid where every line is a row of the board:
+//!
+//! ```text
+//! ########
+//! #......#
+//! #.>....#
+//! ########
+//! ```
+//!
+//! `#` is a wall, `.` is floor and the start cell of the snake head is one of
+//! `>`, `<`, `^`, `v` for the direction it is facing, or `S` for right.
+
+use std::fmt;
+
+use crate::simulation::{Cell, Direction};
+
+pub const BUILTIN_LEVELS: &[(&str, &str)] = &[
+    ("box", include_str!("../levels/box.txt")),
+    ("pillars", include_str!("../levels/pillars.txt")),
+];
+
+const WALL: char = '#';
+const FLOOR: char = '.';
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Level {
+    pub cols: i32,
+    pub rows: i32,
+    pub start: Cell,
+    pub start_dir: Direction,
+    walls: Vec<bool>,
+    /// Name of the built-in level this was made from, if any.
+    name: Option<&'static str>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LevelError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl Level {
+    pub fn builtin(name: &str) -> Option<Level> {
+        let (name, text) = BUILTIN_LEVELS
+            .iter()
+            .find(|(level_name, _)| *level_name == name)?;
+        let level: Level = text.parse().expect("built-in levels are valid");
+        Some(Level {
+            name: Some(name),
+            ..level
+        })
+    }
+
+    /// Name of the built-in level this was made from, if any.
+    pub fn builtin_name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    pub fn is_wall(&self, cell: &Cell) -> bool {
+        (0..self.cols).contains(&cell.x)
+            && (0..self.rows).contains(&cell.y)
+            && self.walls[(cell.y * self.cols + cell.x) as usize]
+    }
+
+    pub fn walls(&self) -> impl Iterator<Item = Cell> + '_ {
+        self.walls
+            .iter()
+            .enumerate()
+            .filter(|(_, wall)| **wall)
+            .map(|(i, _)| Cell {
+                x: i as i32 % self.cols,
+                y: i as i32 / self.cols,
+            })
+    }
+}
+
+fn start_dir(c: char) -> Option<Direction> {
+    match c {
+        'S' | '>' => Some(Direction::Right),
+        '<' => Some(Direction::Left),
+        '^' => Some(Direction::Up),
+        'v' => Some(Direction::Down),
+        _ => None,
+    }
+}
+
+fn start_char(dir: Direction) -> char {
+    match dir {
+        Direction::Right => '>',
+        Direction::Left => '<',
+        Direction::Up => '^',
+        Direction::Down => 'v',
+    }
+}
+
+impl std::str::FromStr for Level {
+    type Err = LevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |line: usize, column: usize, message: &str| LevelError {
+            line,
+            column,
+            message: message.to_string(),
+        };
+
+        let lines: Vec<&str> = s.trim_end().lines().map(|line| line.trim_end()).collect();
+        let cols = lines.first().map_or(0, |line| line.chars().count());
+        if cols == 0 {
+            return Err(error(1, 1, "level is empty"));
+        }
+
+        let mut walls = Vec::with_capacity(cols * lines.len());
+        let mut start = None;
+        for (y, line) in lines.iter().enumerate() {
+            let mut width = 0;
+            for (x, c) in line.chars().enumerate() {
+                width += 1;
+                if x >= cols {
+                    return Err(error(y + 1, x + 1, "row is longer than the first one"));
+                }
+                match c {
+                    WALL => walls.push(true),
+                    FLOOR => walls.push(false),
+                    _ => {
+                        let dir = start_dir(c)
+                            .ok_or_else(|| error(y + 1, x + 1, &format!("unknown tile `{}`", c)))?;
+                        if start.is_some() {
+                            return Err(error(y + 1, x + 1, "more than one start"));
+                        }
+                        start = Some((x, y, dir));
+                        walls.push(false);
+                    }
+                }
+            }
+            if width < cols {
+                return Err(error(y + 1, width + 1, "row is shorter than the first one"));
+            }
+        }
+
+        let (x, y, start_dir) = start.ok_or_else(|| error(lines.len(), 1, "missing start"))?;
+        let level = Level {
+            cols: cols as i32,
+            rows: lines.len() as i32,
+            start: Cell {
+                x: x as i32,
+                y: y as i32,
+            },
+            start_dir,
+            walls,
+            name: None,
+        };
+
+        let behind = level.start + (!start_dir).into();
+        if !(0..level.cols).contains(&behind.x)
+            || !(0..level.rows).contains(&behind.y)
+            || level.is_wall(&behind)
+        {
+            return Err(error(
+                y + 1,
+                x + 1,
+                "no room for the snake behind the start",
+            ));
+        }
+
+        Ok(level)
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.rows {
+            for x in 0..self.cols {
+                let cell = Cell { x, y };
+                let c = if cell == self.start {
+                    start_char(self.start_dir)
+                } else if self.is_wall(&cell) {
+                    WALL
+                } else {
+                    FLOOR
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_levels_parse() {
+        for (name, _) in BUILTIN_LEVELS {
+            let level = Level::builtin(name).unwrap();
+            assert_eq!(level.builtin_name(), Some(*name));
+            let parsed: Level = level.to_string().parse().unwrap();
+            assert_eq!(parsed.builtin_name(), None);
+            assert_eq!(parsed.to_string(), level.to_string());
+        }
+    }
+
+    #[test]
+    fn errors_point_at_the_tile() {
+        let error = |text: &str| {
+            let err = text.parse::<Level>().unwrap_err();
+            (err.line, err.column, err.message)
+        };
+        assert_eq!(
+            error("#####\n#.>.#\n#.x.#\n#####"),
+            (3, 3, "unknown tile `x`".to_string())
+        );
+        assert_eq!(
+            error("#####\n#.>.#\n#...\n#####"),
+            (3, 5, "row is shorter than the first one".to_string())
+        );
+        assert_eq!(
+            error("####\n#.>.#\n####"),
+            (2, 5, "row is longer than the first one".to_string())
+        );
+        assert_eq!(
+            error("#####\n#.>.#\n#.<.#\n#####"),
+            (3, 3, "more than one start".to_string())
+        );
+        assert_eq!(
+            error("#####\n#>..#\n#####"),
+            (2, 2, "no room for the snake behind the start".to_string())
+        );
+        assert_eq!(
+            error("####\n#..#\n####"),
+            (3, 1, "missing start".to_string())
+        );
+    }
+}
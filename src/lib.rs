@@ -1,88 +1,208 @@
-use std::cell::RefCell;
+use std::{cell::RefCell, rc::Rc};
 
 use wasm_bindgen::prelude::*;
-use web_sys::{KeyboardEvent, console};
-
-mod game;
-use game::*;
-mod render;
-mod utils;
+use web_sys::js_sys::Math;
+
+#[cfg(feature = "dynamic_camera")]
+pub mod camera;
+pub mod config;
+pub mod controller;
+pub mod food;
+pub mod game;
+pub mod gamepad;
+pub mod handle;
+pub mod keymap;
+pub mod level;
+use config::GameConfig;
+use controller::Strategy;
+use handle::{Mount, SnakeHandle, mount};
+use keymap::{Keymap, KeymapError};
+pub mod render;
+pub mod replay;
+pub mod save;
+pub mod scores;
+pub mod settings;
+pub mod simulation;
+pub mod theme;
+pub mod touch;
+pub mod utils;
 use render::*;
+use scores::LocalStorageScores;
+use settings::LocalStorageSettings;
+use theme::{Theme, ThemeError};
+use utils::log;
 
 thread_local! {
-    static GAME: RefCell<Option<Game<WebPlatformRenderer>>> = RefCell::new(None);
-    static PREV_TIMESTAMP: RefCell<f32> = RefCell::new(0.0);
+    /// The game on the `canvas` element of the page, if it has one.
+    static PAGE: RefCell<Option<Rc<RefCell<Mount>>>> = const { RefCell::new(None) };
 }
 
 #[wasm_bindgen(start)]
 fn main() {
     console_error_panic_hook::set_once();
 
-    let keydown = Closure::wrap(Box::new(move |e: KeyboardEvent| {
-        GAME.with(|game| {
-            let mut game_ref = game.borrow_mut();
-            let game = game_ref.as_mut().unwrap();
-            game.keydown(&e.key());
-        });
-    }) as Box<dyn FnMut(_)>);
-
-    document()
-        .add_event_listener_with_callback("keydown", keydown.as_ref().unchecked_ref())
-        .unwrap();
-
-    keydown.forget();
-
-    let canvas = document().get_element_by_id("canvas").unwrap();
+    // Pages without one mount their games through `SnakeHandle`.
+    let Some(canvas) = document().get_element_by_id("canvas") else {
+        return;
+    };
     let canvas: web_sys::HtmlCanvasElement = canvas
         .dyn_into::<web_sys::HtmlCanvasElement>()
         .map_err(|_| ())
         .unwrap();
-    let ctx = canvas
-        .get_context("2d")
-        .unwrap()
-        .unwrap()
-        .dyn_into::<web_sys::CanvasRenderingContext2d>()
-        .unwrap();
 
-    GAME.with(|game| {
-        let mut g = Game::new(WebPlatformRenderer::new(ctx));
-        g.restart(canvas.width(), canvas.height());
-        *game.borrow_mut() = Some(g);
-    });
+    let seed = query_param("seed")
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or_else(random_seed);
+    log(&format!("seed = {}", seed));
 
-    game_loop_fn_start();
+    let config = GameConfig::default()
+        .with_params(query_param)
+        .unwrap_or_else(|err| {
+            log(&format!("ignoring the board settings of the page: {}", err));
+            GameConfig::default()
+        });
+    let page = mount(canvas, config, seed).unwrap();
+    {
+        let mount = &mut *page.borrow_mut();
+        let g = &mut mount.game;
+        for (player, slot) in query_param("bots")
+            .unwrap_or_default()
+            .split(',')
+            .take(simulation::MAX_PLAYERS)
+            .enumerate()
+        {
+            if let Ok(strategy) = slot.parse::<Strategy>() {
+                g.set_controller(player, Some(strategy.controller()));
+            }
+        }
+        for (pad, player) in query_param("pads")
+            .unwrap_or_default()
+            .split(',')
+            .enumerate()
+        {
+            if let Some(slot) = player.parse::<usize>().ok().and_then(|n| n.checked_sub(1)) {
+                mount.gamepads.assign(pad, slot);
+            }
+        }
+        if let Some(settings) = LocalStorageSettings::new() {
+            g.set_settings_store(Box::new(settings));
+        }
+        if let Some(scores) = LocalStorageScores::new() {
+            g.set_score_store(Box::new(scores));
+        }
+        if let Some(name) = query_param("name") {
+            g.set_player_name(0, &name);
+        }
+        if g.can_resume()
+            && window()
+                .confirm_with_message("Pick up the last game where you left it?")
+                .unwrap_or(false)
+        {
+            g.resume();
+        }
+        if let Some(theme) = query_param("theme").and_then(|name| Theme::builtin(&name)) {
+            g.set_theme(theme);
+        }
+    }
+    PAGE.with(|mount| *mount.borrow_mut() = Some(page));
 }
 
-fn game_loop_fn_start() {
-    window()
-        .request_animation_frame(
-            Closure::wrap(Box::new(|ts| game_loop_fn(ts)) as Box<dyn FnMut(f32)>)
-                .into_js_value()
-                .unchecked_ref(),
-        )
-        .unwrap();
+/// Calls `f` with the game on the `canvas` element of the page.
+fn with_page<R>(f: impl FnOnce(&mut Mount) -> R) -> Result<R, JsValue> {
+    PAGE.with(|page| {
+        let page = page.borrow();
+        let mount = page
+            .as_ref()
+            .ok_or_else(|| JsValue::from_str("no game on a `canvas` element"))?;
+        Ok(f(&mut mount.borrow_mut()))
+    })
 }
 
-fn game_loop_fn(timestamp: f32) {
-    PREV_TIMESTAMP.with(|prev| {
-        let dt = (timestamp - *prev.borrow()) as f32 / 1000.0;
-        *prev.borrow_mut() = timestamp;
+/// A handle on the game on the `canvas` element of the page, if it has one.
+#[wasm_bindgen]
+pub fn page_game() -> Option<SnakeHandle> {
+    PAGE.with(|page| page.borrow().clone().map(SnakeHandle::from_mount))
+}
 
-        GAME.with(|game| {
-            let mut game_ref = game.borrow_mut();
-            let game = game_ref.as_mut().unwrap();
-            game.update(dt);
-            game.render();
-        });
-    });
+/// Lets gamepad `pad`, counting from 0 like the browser does, steer the
+/// snake of `player`, counting from 1.
+#[wasm_bindgen]
+pub fn set_gamepad_player(pad: usize, player: usize) -> Result<(), JsValue> {
+    let slot = player
+        .checked_sub(1)
+        .filter(|slot| *slot < simulation::MAX_PLAYERS)
+        .ok_or_else(|| JsValue::from_str(&format!("no player {}", player)))?;
+    with_page(|mount| mount.gamepads.assign(pad, slot))
+}
 
-    game_loop_fn_start();
+/// Sets the name the high scores of `player`, counting from 1, go under.
+#[wasm_bindgen]
+pub fn set_player_name(player: usize, name: &str) -> Result<(), JsValue> {
+    let slot = player
+        .checked_sub(1)
+        .filter(|slot| *slot < simulation::MAX_PLAYERS)
+        .ok_or_else(|| JsValue::from_str(&format!("no player {}", player)))?;
+    with_page(|mount| mount.game.set_player_name(slot, name))
 }
 
-fn window() -> web_sys::Window {
+/// The current frame as a standalone SVG document.
+#[wasm_bindgen]
+pub fn export_svg() -> Result<String, JsValue> {
+    with_page(|mount| {
+        let game = &mount.game;
+        let svg = SvgRenderer::new(game.config().width(), game.config().height());
+        game.render_to(&svg);
+        svg.document()
+    })
+}
+
+/// Switches to a built-in theme by name, or to a theme in the text format.
+#[wasm_bindgen]
+pub fn set_theme(theme: &str) -> Result<(), JsValue> {
+    let theme = match Theme::builtin(theme) {
+        Some(theme) => theme,
+        None => theme
+            .parse()
+            .map_err(|err: ThemeError| JsValue::from_str(&err.to_string()))?,
+    };
+    with_page(|mount| mount.game.set_theme(theme))
+}
+
+/// The key bindings in the keymap text format.
+#[wasm_bindgen]
+pub fn keymap() -> Result<String, JsValue> {
+    with_page(|mount| mount.game.keymap().to_string())
+}
+
+/// Rebinds the actions listed in `keymap`, in the keymap text format, and
+/// remembers the bindings for the next visit.
+#[wasm_bindgen]
+pub fn set_keymap(keymap: &str) -> Result<(), JsValue> {
+    let keymap: Keymap = keymap
+        .parse()
+        .map_err(|err: KeymapError| JsValue::from_str(&err.to_string()))?;
+    with_page(|mount| mount.game.set_keymap(keymap))
+}
+
+pub(crate) fn window() -> web_sys::Window {
     web_sys::window().expect("no global `window` exists")
 }
 
-fn document() -> web_sys::Document {
+pub(crate) fn document() -> web_sys::Document {
     window().document().expect("no document")
 }
+
+pub(crate) fn random_seed() -> u64 {
+    let hi = (Math::random() * u32::MAX as f64) as u64;
+    let lo = (Math::random() * u32::MAX as f64) as u64;
+    (hi << 32) | lo
+}
+
+/// The decoded value of `name` in the query of the page, so that
+/// `?name=Ada%20Lovelace` and `?name=Ada+Lovelace` both give `Ada Lovelace`.
+fn query_param(name: &str) -> Option<String> {
+    let search = window().location().search().ok()?;
+    web_sys::UrlSearchParams::new_with_str(&search)
+        .ok()?
+        .get(name)
+}
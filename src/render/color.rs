@@ -0,0 +1,158 @@
+use std::fmt;
+
+/// An sRGB colour with straight (not premultiplied) alpha, eight bits a channel.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(0xFF, 0xFF, 0xFF);
+    pub const RED: Color = Color::rgb(0xFF, 0, 0);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::rgba(r, g, b, 0xFF)
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Parses `#rrggbb`, or `#rrggbbaa` for a colour that is not opaque.
+    pub fn from_hex(s: &str) -> Option<Self> {
+        let hex = s.strip_prefix('#')?;
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) || !matches!(hex.len(), 6 | 8) {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok();
+        let a = if hex.len() == 8 { channel(3)? } else { 0xFF };
+        Some(Self::rgba(channel(0)?, channel(1)?, channel(2)?, a))
+    }
+
+    /// `#rrggbb`, or `#rrggbbaa` when the colour is not opaque, which CSS reads too.
+    pub fn to_hex(self) -> String {
+        if self.is_opaque() {
+            format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+        }
+    }
+
+    /// The colour as a CSS `<color>`, e.g. for a canvas fill style.
+    pub fn to_css(self) -> String {
+        self.to_hex()
+    }
+
+    /// Channels in memory order, as in an RGBA image.
+    pub const fn to_bytes(self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
+    pub const fn from_bytes([r, g, b, a]: [u8; 4]) -> Self {
+        Self::rgba(r, g, b, a)
+    }
+
+    pub fn is_opaque(self) -> bool {
+        self.a == 0xFF
+    }
+
+    pub fn with_alpha(self, a: u8) -> Self {
+        Self { a, ..self }
+    }
+
+    /// The same colour with an alpha of `a` from 0 to 1.
+    pub fn with_opacity(self, a: f32) -> Self {
+        self.with_alpha((a.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    pub fn opacity(self) -> f32 {
+        self.a as f32 / 255.0
+    }
+
+    /// Blends every channel, alpha included, from `self` at 0 to `other` at 1.
+    pub fn lerp(self, other: Color, t: f32) -> Self {
+        let lerp = |c1: u8, c2: u8| (c1 as f32 + (c2 as f32 - c1 as f32) * t).round() as u8;
+        Self::rgba(
+            lerp(self.r, other.r),
+            lerp(self.g, other.g),
+            lerp(self.b, other.b),
+            lerp(self.a, other.a),
+        )
+    }
+
+    /// Draws `self` over `dst` with source-over compositing, which is a plain
+    /// sum once both colours are premultiplied by their alpha.
+    pub fn blend_over(self, dst: Color) -> Self {
+        match self.a {
+            0 => return dst,
+            0xFF => return self,
+            _ => {}
+        }
+        let sa = self.opacity();
+        let da = dst.opacity() * (1.0 - sa);
+        let out_a = sa + da;
+        let channel = |s: u8, d: u8| ((s as f32 * sa + d as f32 * da) / out_a).round() as u8;
+        Self::rgba(
+            channel(self.r, dst.r),
+            channel(self.g, dst.g),
+            channel(self.b, dst.b),
+            (out_a * 255.0).round() as u8,
+        )
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_round_trip() {
+        assert_eq!(
+            Color::from_hex("#ffa631"),
+            Some(Color::rgb(0xFF, 0xA6, 0x31))
+        );
+        assert_eq!(
+            Color::from_hex("#ffa63180"),
+            Some(Color::rgba(0xFF, 0xA6, 0x31, 0x80))
+        );
+        assert_eq!(Color::rgba(0xFF, 0xA6, 0x31, 0x80).to_hex(), "#ffa63180");
+        assert_eq!(Color::rgb(0xFF, 0xA6, 0x31).to_hex(), "#ffa631");
+        assert_eq!(Color::from_hex("ffa631"), None);
+        assert_eq!(Color::from_hex("#ffa6"), None);
+        assert_eq!(Color::from_hex("#ffa63g"), None);
+    }
+
+    #[test]
+    fn lerp_keeps_channels_apart() {
+        let from = Color::rgba(0xFF, 0, 0x10, 0xFF);
+        let to = Color::rgba(0, 0xFF, 0x10, 0x00);
+        assert_eq!(from.lerp(to, 0.0), from);
+        assert_eq!(from.lerp(to, 1.0), to);
+        assert_eq!(from.lerp(to, 0.5), Color::rgba(0x80, 0x80, 0x10, 0x80));
+    }
+
+    #[test]
+    fn blend_over() {
+        let dst = Color::rgb(0, 0, 0xFF);
+        assert_eq!(Color::RED.blend_over(dst), Color::RED);
+        assert_eq!(Color::TRANSPARENT.blend_over(dst), dst);
+        assert_eq!(
+            Color::RED.with_alpha(0x80).blend_over(dst),
+            Color::rgb(0x80, 0, 0x7F)
+        );
+        // Over a transparent pixel the colour keeps its own channels.
+        let half = Color::RED.with_alpha(0x80);
+        assert_eq!(half.blend_over(Color::TRANSPARENT), half);
+    }
+}
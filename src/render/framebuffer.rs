@@ -0,0 +1,197 @@
+use std::{cell::RefCell, rc::Rc};
+
+use super::{
+    Color, PlatformRenderer,
+    font::{GLYPH_HEIGHT, GLYPH_WIDTH, glyph},
+};
+
+// Constants
+const GLYPH_ADVANCE: u32 = GLYPH_WIDTH + 1;
+/// Share of the font size taken by a capital letter, close to what Verdana gets on a canvas.
+const CAP_HEIGHT_PERCENT: f32 = 0.7;
+
+/// An RGBA image in memory, four bytes per pixel, rows top to bottom.
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let i = ((y * self.width + x) * 4) as usize;
+        Color::from_bytes(self.pixels[i..i + 4].try_into().unwrap())
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&color.to_bytes());
+        }
+    }
+
+    /// Draws `color` over the pixel with source-over alpha blending.
+    fn blend(&mut self, x: i32, y: i32, color: Color) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        let i = ((y as u32 * self.width + x as u32) * 4) as usize;
+        let dst = Color::from_bytes(self.pixels[i..i + 4].try_into().unwrap());
+        self.pixels[i..i + 4].copy_from_slice(&color.blend_over(dst).to_bytes());
+    }
+
+    fn fill_rect(&mut self, x: i32, y: i32, w: i32, h: i32, color: Color) {
+        // Like a canvas, a negative size extends the rect the other way.
+        let (x, w) = if w < 0 { (x + w, -w) } else { (x, w) };
+        let (y, h) = if h < 0 { (y + h, -h) } else { (y, h) };
+        let x1 = x.max(0);
+        let y1 = y.max(0);
+        let x2 = (x + w).min(self.width as i32);
+        let y2 = (y + h).min(self.height as i32);
+        for py in y1..y2 {
+            for px in x1..x2 {
+                self.blend(px, py, color);
+            }
+        }
+    }
+}
+
+/// A [`PlatformRenderer`] drawing into a [`Framebuffer`], for native builds and tests.
+#[derive(Clone)]
+pub struct FramebufferRenderer {
+    framebuffer: Rc<RefCell<Framebuffer>>,
+}
+
+impl FramebufferRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            framebuffer: Rc::new(RefCell::new(Framebuffer::new(width, height))),
+        }
+    }
+
+    pub fn framebuffer(&self) -> std::cell::Ref<'_, Framebuffer> {
+        self.framebuffer.borrow()
+    }
+
+    pub fn clear(&self, color: Color) {
+        self.framebuffer.borrow_mut().clear(color);
+    }
+}
+
+impl PlatformRenderer for FramebufferRenderer {
+    fn fill_rect(&self, x: i32, y: i32, w: i32, h: i32, color: Color) {
+        self.framebuffer.borrow_mut().fill_rect(x, y, w, h, color);
+    }
+
+    fn stroke_rect(&self, x: i32, y: i32, w: i32, h: i32, color: Color) {
+        let mut fb = self.framebuffer.borrow_mut();
+        fb.fill_rect(x, y, w, 1, color);
+        if h > 1 {
+            fb.fill_rect(x, y + h - 1, w, 1, color);
+        }
+        if h > 2 {
+            fb.fill_rect(x, y + 1, 1, h - 2, color);
+            if w > 1 {
+                fb.fill_rect(x + w - 1, y + 1, 1, h - 2, color);
+            }
+        }
+    }
+
+    /// Draws the text left aligned with `y` on the baseline, as a canvas does by default.
+    fn fill_text(&self, x: i32, y: i32, text: &str, font_size: u32, color: Color) {
+        let scale =
+            ((font_size as f32 * CAP_HEIGHT_PERCENT / GLYPH_HEIGHT as f32).round() as i32).max(1);
+        let top = y - GLYPH_HEIGHT as i32 * scale;
+        let mut fb = self.framebuffer.borrow_mut();
+        for (i, c) in text.chars().enumerate() {
+            let left = x + i as i32 * GLYPH_ADVANCE as i32 * scale;
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for col in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
+                        fb.fill_rect(
+                            left + col as i32 * scale,
+                            top + row as i32 * scale,
+                            scale,
+                            scale,
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The pixels of the renderer as rows of `#` for `color` and `.` for anything else.
+    fn picture(renderer: &FramebufferRenderer, color: Color) -> Vec<String> {
+        let fb = renderer.framebuffer();
+        (0..fb.height())
+            .map(|y| {
+                (0..fb.width())
+                    .map(|x| if fb.pixel(x, y) == color { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rects_are_filled_and_stroked_within_bounds() {
+        let renderer = FramebufferRenderer::new(6, 4);
+        renderer.clear(Color::BLACK);
+        renderer.fill_rect(4, 3, -2, -2, Color::RED);
+        renderer.fill_rect(5, -1, 4, 2, Color::RED);
+        assert_eq!(
+            picture(&renderer, Color::RED),
+            [".....#", "..##..", "..##..", "......"]
+        );
+
+        let renderer = FramebufferRenderer::new(6, 4);
+        renderer.stroke_rect(0, 0, 5, 4, Color::WHITE);
+        assert_eq!(
+            picture(&renderer, Color::WHITE),
+            ["#####.", "#...#.", "#...#.", "#####."]
+        );
+    }
+
+    #[test]
+    fn text_is_drawn_on_the_baseline() {
+        let renderer = FramebufferRenderer::new(GLYPH_ADVANCE * 2, GLYPH_HEIGHT + 2);
+        // Small enough for one pixel per bit of the glyph.
+        renderer.fill_text(0, GLYPH_HEIGHT as i32 + 1, "A", 10, Color::WHITE);
+        let picture = picture(&renderer, Color::WHITE);
+        assert_eq!(picture[0], ".".repeat(GLYPH_ADVANCE as usize * 2));
+        for (row, bits) in glyph('A').iter().enumerate() {
+            let expected: String = (0..GLYPH_ADVANCE * 2)
+                .map(|col| {
+                    let lit = col < GLYPH_WIDTH && bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0;
+                    if lit { '#' } else { '.' }
+                })
+                .collect();
+            assert_eq!(picture[row + 1], expected, "row {}", row);
+        }
+        assert!(picture[1..].iter().any(|row| row.contains('#')));
+    }
+}
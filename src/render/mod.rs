@@ -1,8 +1,21 @@
+mod color;
+mod font;
+mod framebuffer;
+mod recording;
+mod svg;
+#[cfg(not(target_arch = "wasm32"))]
+mod terminal;
 mod web;
+pub use color::*;
+pub use framebuffer::*;
+pub use recording::*;
+pub use svg::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use terminal::*;
 pub use web::*;
 
 pub trait PlatformRenderer: Clone {
-    fn fill_rect(&self, x: i32, y: i32, w: i32, h: i32, color: u32);
-    fn stroke_rect(&self, x: i32, y: i32, w: i32, h: i32, color: u32);
-    fn fill_text(&self, x: i32, y: i32, text: &str, font_size: u32, color: u32);
+    fn fill_rect(&self, x: i32, y: i32, w: i32, h: i32, color: Color);
+    fn stroke_rect(&self, x: i32, y: i32, w: i32, h: i32, color: Color);
+    fn fill_text(&self, x: i32, y: i32, text: &str, font_size: u32, color: Color);
 }
@@ -0,0 +1,435 @@
+//! Recording of draw calls instead of drawing them.
+//!
+//! A [`RecordingRenderer`] collects every call into a [`Frame`], which can be
+//! exported as JSON for reading and diffing, or packed into a compact binary
+//! stream and drawn again on any other [`PlatformRenderer`].
+//!
+//! The binary stream starts with `SNKD`, a version byte and the number of
+//! frames. Each frame is its number of commands followed by the commands, one
+//! opcode byte each (`0` fill rect, `1` stroke rect, `2` fill text) and then
+//! the arguments in call order. Integers are LEB128 varints, zigzag encoded
+//! when signed, colours are four bytes in RGBA order and text is its byte
+//! length followed by UTF-8.
+
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use super::{Color, PlatformRenderer};
+
+pub const DRAW_STREAM_VERSION: u8 = 1;
+const DRAW_STREAM_MAGIC: &[u8; 4] = b"SNKD";
+
+const OP_FILL_RECT: u8 = 0;
+const OP_STROKE_RECT: u8 = 1;
+const OP_FILL_TEXT: u8 = 2;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DrawCommand {
+    FillRect {
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        color: Color,
+    },
+    StrokeRect {
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        color: Color,
+    },
+    FillText {
+        x: i32,
+        y: i32,
+        text: String,
+        font_size: u32,
+        color: Color,
+    },
+}
+
+/// The draw calls of one rendered frame, in the order they were made.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Frame {
+    pub commands: Vec<DrawCommand>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DecodeError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    UnknownCommand(u8),
+    InvalidText,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::BadMagic => write!(f, "not a draw stream"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported draw stream version {}", version)
+            }
+            DecodeError::Truncated => write!(f, "draw stream ends early"),
+            DecodeError::UnknownCommand(op) => write!(f, "unknown draw command {}", op),
+            DecodeError::InvalidText => write!(f, "text is not valid UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl Frame {
+    /// Issues the recorded calls again on another renderer.
+    pub fn draw<P: PlatformRenderer>(&self, renderer: &P) {
+        for command in &self.commands {
+            match command {
+                DrawCommand::FillRect { x, y, w, h, color } => {
+                    renderer.fill_rect(*x, *y, *w, *h, *color)
+                }
+                DrawCommand::StrokeRect { x, y, w, h, color } => {
+                    renderer.stroke_rect(*x, *y, *w, *h, *color)
+                }
+                DrawCommand::FillText {
+                    x,
+                    y,
+                    text,
+                    font_size,
+                    color,
+                } => renderer.fill_text(*x, *y, text, *font_size, *color),
+            }
+        }
+    }
+
+    /// One JSON object per command, colours written as CSS hex like `"#rrggbb"`.
+    pub fn to_json(&self) -> String {
+        let commands: Vec<String> = self
+            .commands
+            .iter()
+            .map(|command| match command {
+                DrawCommand::FillRect { x, y, w, h, color } => format!(
+                    r#"{{"op":"fill_rect","x":{},"y":{},"w":{},"h":{},"color":"{}"}}"#,
+                    x,
+                    y,
+                    w,
+                    h,
+                    json_color(*color)
+                ),
+                DrawCommand::StrokeRect { x, y, w, h, color } => format!(
+                    r#"{{"op":"stroke_rect","x":{},"y":{},"w":{},"h":{},"color":"{}"}}"#,
+                    x,
+                    y,
+                    w,
+                    h,
+                    json_color(*color)
+                ),
+                DrawCommand::FillText {
+                    x,
+                    y,
+                    text,
+                    font_size,
+                    color,
+                } => format!(
+                    r#"{{"op":"fill_text","x":{},"y":{},"text":"{}","font_size":{},"color":"{}"}}"#,
+                    x,
+                    y,
+                    json_escape(text),
+                    font_size,
+                    json_color(*color)
+                ),
+            })
+            .collect();
+        format!("[{}]", commands.join(","))
+    }
+}
+
+pub fn frames_to_json(frames: &[Frame]) -> String {
+    let frames: Vec<String> = frames.iter().map(Frame::to_json).collect();
+    format!("[{}]", frames.join(",\n"))
+}
+
+fn json_color(color: Color) -> String {
+    color.to_hex()
+}
+
+fn json_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    loop {
+        let byte = (n & 0x7F) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn write_int(out: &mut Vec<u8>, n: i32) {
+    write_varint(out, ((n << 1) ^ (n >> 31)) as u32 as u64);
+}
+
+fn write_rect(out: &mut Vec<u8>, op: u8, rect: [i32; 4], color: Color) {
+    out.push(op);
+    for n in rect {
+        write_int(out, n);
+    }
+    out.extend_from_slice(&color.to_bytes());
+}
+
+pub fn encode_frames(frames: &[Frame]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(DRAW_STREAM_MAGIC);
+    out.push(DRAW_STREAM_VERSION);
+    write_varint(&mut out, frames.len() as u64);
+    for frame in frames {
+        write_varint(&mut out, frame.commands.len() as u64);
+        for command in &frame.commands {
+            match command {
+                DrawCommand::FillRect { x, y, w, h, color } => {
+                    write_rect(&mut out, OP_FILL_RECT, [*x, *y, *w, *h], *color)
+                }
+                DrawCommand::StrokeRect { x, y, w, h, color } => {
+                    write_rect(&mut out, OP_STROKE_RECT, [*x, *y, *w, *h], *color)
+                }
+                DrawCommand::FillText {
+                    x,
+                    y,
+                    text,
+                    font_size,
+                    color,
+                } => {
+                    out.push(OP_FILL_TEXT);
+                    write_int(&mut out, *x);
+                    write_int(&mut out, *y);
+                    write_varint(&mut out, text.len() as u64);
+                    out.extend_from_slice(text.as_bytes());
+                    write_varint(&mut out, *font_size as u64);
+                    out.extend_from_slice(&color.to_bytes());
+                }
+            }
+        }
+    }
+    out
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn bytes(&mut self, n: usize) -> Result<&[u8], DecodeError> {
+        if self.data.len() < n {
+            return Err(DecodeError::Truncated);
+        }
+        let (head, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let mut n = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            n |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(DecodeError::Truncated)
+    }
+
+    fn int(&mut self) -> Result<i32, DecodeError> {
+        let n = self.varint()? as u32;
+        Ok(((n >> 1) as i32) ^ -((n & 1) as i32))
+    }
+
+    fn color(&mut self) -> Result<Color, DecodeError> {
+        let bytes = self.bytes(4)?;
+        Ok(Color::from_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn rect(&mut self) -> Result<(i32, i32, i32, i32), DecodeError> {
+        Ok((self.int()?, self.int()?, self.int()?, self.int()?))
+    }
+}
+
+pub fn decode_frames(data: &[u8]) -> Result<Vec<Frame>, DecodeError> {
+    let mut reader = Reader { data };
+    if reader.bytes(DRAW_STREAM_MAGIC.len()).ok() != Some(DRAW_STREAM_MAGIC.as_slice()) {
+        return Err(DecodeError::BadMagic);
+    }
+    let version = reader.byte()?;
+    if version != DRAW_STREAM_VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+
+    let frame_count = reader.varint()?;
+    let mut frames = Vec::new();
+    for _ in 0..frame_count {
+        let command_count = reader.varint()?;
+        let mut frame = Frame::default();
+        for _ in 0..command_count {
+            let command = match reader.byte()? {
+                OP_FILL_RECT => {
+                    let (x, y, w, h) = reader.rect()?;
+                    let color = reader.color()?;
+                    DrawCommand::FillRect { x, y, w, h, color }
+                }
+                OP_STROKE_RECT => {
+                    let (x, y, w, h) = reader.rect()?;
+                    let color = reader.color()?;
+                    DrawCommand::StrokeRect { x, y, w, h, color }
+                }
+                OP_FILL_TEXT => {
+                    let x = reader.int()?;
+                    let y = reader.int()?;
+                    let len = reader.varint()? as usize;
+                    let text = std::str::from_utf8(reader.bytes(len)?)
+                        .map_err(|_| DecodeError::InvalidText)?
+                        .to_string();
+                    let font_size = reader.varint()? as u32;
+                    let color = reader.color()?;
+                    DrawCommand::FillText {
+                        x,
+                        y,
+                        text,
+                        font_size,
+                        color,
+                    }
+                }
+                op => return Err(DecodeError::UnknownCommand(op)),
+            };
+            frame.commands.push(command);
+        }
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// A [`PlatformRenderer`] that keeps the calls of the current frame instead of drawing them.
+#[derive(Clone, Default)]
+pub struct RecordingRenderer {
+    frame: Rc<RefCell<Frame>>,
+}
+
+impl RecordingRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hands over the calls recorded so far and starts a new frame.
+    pub fn take_frame(&self) -> Frame {
+        self.frame.take()
+    }
+
+    fn record(&self, command: DrawCommand) {
+        self.frame.borrow_mut().commands.push(command);
+    }
+}
+
+impl PlatformRenderer for RecordingRenderer {
+    fn fill_rect(&self, x: i32, y: i32, w: i32, h: i32, color: Color) {
+        self.record(DrawCommand::FillRect { x, y, w, h, color });
+    }
+
+    fn stroke_rect(&self, x: i32, y: i32, w: i32, h: i32, color: Color) {
+        self.record(DrawCommand::StrokeRect { x, y, w, h, color });
+    }
+
+    fn fill_text(&self, x: i32, y: i32, text: &str, font_size: u32, color: Color) {
+        self.record(DrawCommand::FillText {
+            x,
+            y,
+            text: text.to_string(),
+            font_size,
+            color,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame() -> Frame {
+        Frame {
+            commands: vec![
+                DrawCommand::FillRect {
+                    x: -3,
+                    y: 0,
+                    w: 100,
+                    h: i32::MAX,
+                    color: Color::rgb(0x18, 0x18, 0x18),
+                },
+                DrawCommand::StrokeRect {
+                    x: i32::MIN,
+                    y: 7,
+                    w: 0,
+                    h: -1,
+                    color: Color::RED,
+                },
+                DrawCommand::FillText {
+                    x: 100,
+                    y: 100,
+                    text: "Score: \"3\" ü".to_string(),
+                    font_size: 48,
+                    color: Color::WHITE,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let frames = vec![frame(), Frame::default(), frame()];
+        assert_eq!(decode_frames(&encode_frames(&frames)), Ok(frames));
+    }
+
+    #[test]
+    fn binary_rejects_bad_input() {
+        let data = encode_frames(&[frame()]);
+        assert_eq!(decode_frames(b"nope"), Err(DecodeError::BadMagic));
+        assert_eq!(
+            decode_frames(&data[..data.len() - 1]),
+            Err(DecodeError::Truncated)
+        );
+        let mut data = data;
+        data[4] = DRAW_STREAM_VERSION + 1;
+        assert_eq!(
+            decode_frames(&data),
+            Err(DecodeError::UnsupportedVersion(DRAW_STREAM_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn json_escapes_text() {
+        let json = frame().to_json();
+        assert!(json.contains(r#""text":"Score: \"3\" ü""#), "{}", json);
+        assert!(json.contains(r##""color":"#181818""##), "{}", json);
+    }
+
+    #[test]
+    fn recorded_frame_draws_the_same_calls() {
+        let recorder = RecordingRenderer::new();
+        frame().draw(&recorder);
+        assert_eq!(recorder.take_frame(), frame());
+        assert_eq!(recorder.take_frame(), Frame::default());
+    }
+}
@@ -0,0 +1,165 @@
+//! Drawing into a standalone SVG document, for sharing crisp screenshots.
+
+use std::{cell::RefCell, fmt::Write, rc::Rc};
+
+use super::{Color, PlatformRenderer};
+
+// Constants
+const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+/// Same family the canvas renderer asks for.
+const FONT_FAMILY: &str = "Verdana";
+
+/// A [`PlatformRenderer`] writing every call as an SVG element, a `<rect>`
+/// for rects and a `<text>` for text.
+#[derive(Clone)]
+pub struct SvgRenderer {
+    width: u32,
+    height: u32,
+    body: Rc<RefCell<String>>,
+}
+
+impl SvgRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            body: Rc::new(RefCell::new(String::new())),
+        }
+    }
+
+    /// The document with everything drawn so far.
+    pub fn document(&self) -> String {
+        format!(
+            "<svg xmlns=\"{}\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n{}</svg>\n",
+            SVG_NAMESPACE,
+            self.body.borrow(),
+            w = self.width,
+            h = self.height,
+        )
+    }
+
+    /// The document with everything drawn so far, starting a new one.
+    pub fn take_document(&self) -> String {
+        let document = self.document();
+        self.body.borrow_mut().clear();
+        document
+    }
+}
+
+/// Splits the colour into `#rrggbb` and the opacity, which is left out when
+/// the colour is opaque.
+fn svg_paint(attr: &str, color: Color) -> String {
+    let mut paint = format!("{}=\"{}\"", attr, color.with_alpha(0xFF).to_hex());
+    if !color.is_opaque() {
+        write!(paint, " {}-opacity=\"{}\"", attr, color.opacity()).unwrap();
+    }
+    paint
+}
+
+/// Like a canvas, a negative size extends the rect the other way.
+fn normalize(start: i32, len: i32) -> (i32, i32) {
+    if len < 0 {
+        (start + len, -len)
+    } else {
+        (start, len)
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+impl PlatformRenderer for SvgRenderer {
+    fn fill_rect(&self, x: i32, y: i32, w: i32, h: i32, color: Color) {
+        let (x, w) = normalize(x, w);
+        let (y, h) = normalize(y, h);
+        writeln!(
+            self.body.borrow_mut(),
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>",
+            x,
+            y,
+            w,
+            h,
+            svg_paint("fill", color)
+        )
+        .unwrap();
+    }
+
+    fn stroke_rect(&self, x: i32, y: i32, w: i32, h: i32, color: Color) {
+        let (x, w) = normalize(x, w);
+        let (y, h) = normalize(y, h);
+        writeln!(
+            self.body.borrow_mut(),
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" {} stroke-width=\"1\"/>",
+            x,
+            y,
+            w,
+            h,
+            svg_paint("stroke", color)
+        )
+        .unwrap();
+    }
+
+    /// Writes the text left aligned with `y` on the baseline, as a canvas does by default.
+    fn fill_text(&self, x: i32, y: i32, text: &str, font_size: u32, color: Color) {
+        writeln!(
+            self.body.borrow_mut(),
+            "<text x=\"{}\" y=\"{}\" font-family=\"{}\" font-size=\"{}\" {}>{}</text>",
+            x,
+            y,
+            FONT_FAMILY,
+            font_size,
+            svg_paint("fill", color),
+            xml_escape(text)
+        )
+        .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_split_into_fill_and_opacity() {
+        let svg = SvgRenderer::new(10, 10);
+        svg.fill_rect(0, 0, 10, 10, Color::rgb(0x12, 0x34, 0x56));
+        svg.fill_rect(5, 5, -2, -3, Color::WHITE.with_alpha(0x80));
+        let document = svg.document();
+        assert!(
+            document.contains(r##"<rect x="0" y="0" width="10" height="10" fill="#123456"/>"##),
+            "{}",
+            document
+        );
+        assert!(
+            document.contains(
+                r##"<rect x="3" y="2" width="2" height="3" fill="#ffffff" fill-opacity="0.5019608"/>"##
+            ),
+            "{}",
+            document
+        );
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        let svg = SvgRenderer::new(10, 10);
+        svg.fill_text(1, 2, "a<b & \"c\"", 12, Color::RED);
+        let document = svg.take_document();
+        assert!(
+            document.contains(">a&lt;b &amp; &quot;c&quot;</text>"),
+            "{}",
+            document
+        );
+        assert!(!svg.take_document().contains("<text"));
+    }
+}
@@ -0,0 +1,238 @@
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::{self, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+};
+
+use super::{Color, FramebufferRenderer, PlatformRenderer};
+
+// Constants
+/// Upper half block: the foreground paints the top pixel, the background the bottom one.
+const HALF_BLOCK: char = '▀';
+/// Character columns per board cell, so that cells come out about square.
+const CHARS_PER_CELL: i32 = 2;
+/// Pixels of a character column in the low resolution image, two above each other.
+const PIXELS_PER_CHAR: i32 = 2;
+
+/// Text written over the image, one entry per character.
+type TextLayer = Vec<Option<(char, Color)>>;
+
+/// Draws into a low resolution image with two pixels per board cell each way,
+/// then prints it with half blocks, so every character shows two pixels.
+#[derive(Clone)]
+pub struct TerminalRenderer {
+    cell_size: i32,
+    image: FramebufferRenderer,
+    text: Rc<RefCell<TextLayer>>,
+    /// What was printed last, so that an unchanged frame is not sent again.
+    last_frame: Rc<RefCell<Vec<u8>>>,
+}
+
+impl TerminalRenderer {
+    pub fn new(cols: i32, rows: i32, cell_size: i32) -> Self {
+        let width = cols * CHARS_PER_CELL;
+        let height = rows * PIXELS_PER_CHAR;
+        Self {
+            cell_size,
+            image: FramebufferRenderer::new(width as u32, height as u32),
+            text: Rc::new(RefCell::new(vec![None; (width * rows) as usize])),
+            last_frame: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// Size of a low resolution pixel in game pixels.
+    fn pixel_size(&self) -> f32 {
+        self.cell_size as f32 / CHARS_PER_CELL as f32
+    }
+
+    /// Maps a span of game pixels to the low resolution pixels whose centers it covers.
+    fn span(&self, start: i32, len: i32) -> (i32, i32) {
+        let (start, len) = if len < 0 {
+            (start + len, -len)
+        } else {
+            (start, len)
+        };
+        let pixel_size = self.pixel_size();
+        let first = (start as f32 / pixel_size - 0.5).ceil() as i32;
+        let end = ((start + len) as f32 / pixel_size - 0.5).ceil() as i32;
+        (first, end - first)
+    }
+
+    /// Prints the frame at the top left of the terminal and clears the text layer.
+    pub fn present(&self, out: &mut impl Write) -> io::Result<()> {
+        let image = self.image.framebuffer();
+        let mut text = self.text.borrow_mut();
+        let width = image.width();
+
+        let mut frame = Vec::new();
+        let mut colors = None;
+        for row in 0..image.height() / PIXELS_PER_CHAR as u32 {
+            queue!(frame, MoveTo(0, row as u16))?;
+            for col in 0..width {
+                let top = image.pixel(col, row * 2);
+                let bottom = image.pixel(col, row * 2 + 1);
+                let (c, fg, bg) = match text[(row * width + col) as usize].take() {
+                    Some((c, color)) => (c, color, top),
+                    None => (HALF_BLOCK, top, bottom),
+                };
+                if colors != Some((fg, bg)) {
+                    queue!(
+                        frame,
+                        SetForegroundColor(terminal_color(fg)),
+                        SetBackgroundColor(terminal_color(bg))
+                    )?;
+                    colors = Some((fg, bg));
+                }
+                queue!(frame, Print(c))?;
+            }
+        }
+        queue!(frame, ResetColor)?;
+
+        let mut last_frame = self.last_frame.borrow_mut();
+        if *last_frame != frame {
+            out.write_all(&frame)?;
+            out.flush()?;
+            *last_frame = frame;
+        }
+        Ok(())
+    }
+}
+
+fn terminal_color(color: Color) -> style::Color {
+    style::Color::Rgb {
+        r: color.r,
+        g: color.g,
+        b: color.b,
+    }
+}
+
+impl PlatformRenderer for TerminalRenderer {
+    fn fill_rect(&self, x: i32, y: i32, w: i32, h: i32, color: Color) {
+        let (x, w) = self.span(x, w);
+        let (y, h) = self.span(y, h);
+        self.image.fill_rect(x, y, w, h, color);
+    }
+
+    /// Outlines are much thinner than a character, so they are left out.
+    fn stroke_rect(&self, _x: i32, _y: i32, _w: i32, _h: i32, _color: Color) {}
+
+    /// Writes the text as characters starting at the character cell holding `(x, y)`.
+    fn fill_text(&self, x: i32, y: i32, text: &str, _font_size: u32, color: Color) {
+        let width = self.image.framebuffer().width() as i32;
+        let mut layer = self.text.borrow_mut();
+        let rows = layer.len() as i32 / width;
+        // `y` is the baseline, so the text belongs to the row just above it.
+        let row_height = self.pixel_size() * PIXELS_PER_CHAR as f32;
+        let row = ((y - 1) as f32 / row_height).floor() as i32;
+        let col = (x as f32 / self.pixel_size()).floor() as i32;
+        if !(0..rows).contains(&row) {
+            return;
+        }
+        for (i, c) in text.chars().enumerate() {
+            let col = col + i as i32;
+            if (0..width).contains(&col) {
+                layer[(row * width + col) as usize] = Some((c, color));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLUE: Color = Color::rgb(0, 0, 0xFF);
+
+    /// The low resolution image as rows of `#` for `color` and `.` for anything else.
+    fn picture(renderer: &TerminalRenderer, color: Color) -> Vec<String> {
+        let image = renderer.image.framebuffer();
+        (0..image.height())
+            .map(|y| {
+                (0..image.width())
+                    .map(|x| if image.pixel(x, y) == color { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// What `present` prints for a character in `fg` over `bg`.
+    fn colored(c: char, fg: Color, bg: Color) -> Vec<u8> {
+        let mut out = Vec::new();
+        queue!(
+            out,
+            SetForegroundColor(terminal_color(fg)),
+            SetBackgroundColor(terminal_color(bg)),
+            Print(c)
+        )
+        .unwrap();
+        out
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    #[test]
+    fn rects_cover_the_pixels_whose_centers_they_hold() {
+        // Two cells of 100 game pixels make four pixels of 50 across, two down.
+        let renderer = TerminalRenderer::new(2, 1, 100);
+        renderer.fill_rect(24, 0, 2, 50, Color::RED);
+        renderer.fill_rect(124, 50, 2, 50, Color::RED);
+        renderer.fill_rect(200, 100, -26, -50, Color::RED);
+        // Holds no pixel center, so it draws nothing.
+        renderer.fill_rect(126, 0, 48, 50, Color::RED);
+        assert_eq!(picture(&renderer, Color::RED), ["#...", "..##"]);
+    }
+
+    #[test]
+    fn half_blocks_show_the_top_pixel_over_the_bottom_one() {
+        let renderer = TerminalRenderer::new(1, 1, 100);
+        renderer.fill_rect(0, 0, 100, 50, Color::RED);
+        renderer.fill_rect(0, 50, 100, 50, BLUE);
+        let mut out = Vec::new();
+        renderer.present(&mut out).unwrap();
+        assert!(contains(&out, &colored(HALF_BLOCK, Color::RED, BLUE)));
+
+        // Text takes the character and keeps the top pixel behind it.
+        renderer.fill_text(50, 100, "!", 10, BLUE);
+        let mut out = Vec::new();
+        renderer.present(&mut out).unwrap();
+        assert!(contains(&out, &colored('!', BLUE, Color::RED)));
+
+        // The text is gone again, and the same frame is not printed twice.
+        let mut out = Vec::new();
+        renderer.present(&mut out).unwrap();
+        assert!(!out.is_empty());
+        let mut again = Vec::new();
+        renderer.present(&mut again).unwrap();
+        assert!(again.is_empty());
+    }
+
+    #[test]
+    fn rects_and_text_off_the_screen_are_clipped() {
+        let renderer = TerminalRenderer::new(2, 1, 100);
+        renderer.fill_rect(-100, -100, 200, 150, Color::RED);
+        renderer.fill_rect(300, 0, 100, 100, Color::RED);
+        renderer.fill_rect(0, -200, 200, 100, Color::RED);
+        assert_eq!(picture(&renderer, Color::RED), ["##..", "...."]);
+
+        renderer.fill_text(150, 100, "abc", 10, BLUE);
+        renderer.fill_text(0, 300, "x", 10, BLUE);
+        renderer.fill_text(0, -50, "x", 10, BLUE);
+        let text: Vec<_> = renderer
+            .text
+            .borrow()
+            .iter()
+            .map(|entry| entry.map(|(c, _)| c))
+            .collect();
+        assert_eq!(text, [None, None, None, Some('a')]);
+    }
+}
@@ -0,0 +1,382 @@
+//! Recording and deterministic playback of a session.
+//!
+//! A replay is the seed the session started with plus every [`Input`] tagged
+//! with the simulation tick it arrived on. The text form looks like this:
+//!
+//! ```text
+//! snake-replay 1
+//! seed 1234
+//! board 16 9 3 wrap 2
+//! 12 U
+//! 15 L 1
+//! 40 P
+//! 40 P
+//! 57 X
+//! end 80
+//! ```
+//!
+//! The optional `board` line holds the columns, rows, initial snake length,
+//! edge policy and number of players. It may be followed by `level <rows>` and
+//! the rows of a level. Inputs are `U`, `D`, `L`, `R` for turns, followed by
+//! the player index for every player but the first, `P` for pause and `X` for
+//! restart.
+//! Ticks count the steps of the current round and start over after `X`.
+
+use std::fmt;
+
+use crate::{
+    config::GameConfig,
+    level::Level,
+    simulation::{Direction, Input, Simulation, State},
+};
+
+pub const REPLAY_VERSION: u32 = 1;
+const REPLAY_MAGIC: &str = "snake-replay";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ReplayEvent {
+    pub tick: u64,
+    pub input: Input,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Replay {
+    pub config: GameConfig,
+    pub seed: u64,
+    pub events: Vec<ReplayEvent>,
+    pub end_tick: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ReplaySummary {
+    pub seed: u64,
+    pub score: u32,
+    pub tick: u64,
+    pub state: State,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ReplayError {
+    MissingHeader,
+    UnsupportedVersion(u32),
+    Parse { line: usize, message: String },
+    Desync { tick: u64 },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::MissingHeader => write!(f, "missing `{}` header", REPLAY_MAGIC),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "unsupported replay version {}", version)
+            }
+            ReplayError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            ReplayError::Desync { tick } => write!(f, "replay desynced at tick {}", tick),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl Replay {
+    pub fn new(config: GameConfig, seed: u64) -> Self {
+        Self {
+            config,
+            seed,
+            events: Vec::new(),
+            end_tick: 0,
+        }
+    }
+
+    pub fn record(&mut self, tick: u64, input: Input) {
+        self.events.push(ReplayEvent { tick, input });
+    }
+
+    /// Runs the replay on a fresh simulation and returns it in its final state.
+    pub fn play(&self) -> Result<Simulation, ReplayError> {
+        let mut sim = Simulation::new(self.config.clone(), self.seed);
+        for event in &self.events {
+            advance_to(&mut sim, event.tick)?;
+            sim.apply_input(event.input);
+        }
+        advance_to(&mut sim, self.end_tick)?;
+        Ok(sim)
+    }
+
+    pub fn verify(&self) -> Result<ReplaySummary, ReplayError> {
+        let sim = self.play()?;
+        Ok(ReplaySummary {
+            seed: sim.seed(),
+            score: sim.score(),
+            tick: sim.tick(),
+            state: sim.state(),
+        })
+    }
+}
+
+fn advance_to(sim: &mut Simulation, tick: u64) -> Result<(), ReplayError> {
+    while sim.tick() < tick {
+        if sim.step().is_none() {
+            return Err(ReplayError::Desync { tick: sim.tick() });
+        }
+    }
+    if sim.tick() > tick {
+        return Err(ReplayError::Desync { tick: sim.tick() });
+    }
+    Ok(())
+}
+
+pub(crate) fn dir_code(dir: Direction) -> char {
+    match dir {
+        Direction::Up => 'U',
+        Direction::Down => 'D',
+        Direction::Left => 'L',
+        Direction::Right => 'R',
+    }
+}
+
+fn input_code(input: Input) -> String {
+    match input {
+        Input::Turn(0, dir) => dir_code(dir).to_string(),
+        Input::Turn(player, dir) => format!("{} {}", dir_code(dir), player),
+        Input::TogglePause => "P".to_string(),
+        Input::Restart => "X".to_string(),
+    }
+}
+
+pub(crate) fn parse_dir(code: char) -> Option<Direction> {
+    match code {
+        'U' => Some(Direction::Up),
+        'D' => Some(Direction::Down),
+        'L' => Some(Direction::Left),
+        'R' => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+fn parse_input(code: &str) -> Option<Input> {
+    let mut fields = code.split(' ');
+    let input = match fields.next()? {
+        "P" => Input::TogglePause,
+        "X" => Input::Restart,
+        dir => {
+            let mut chars = dir.chars();
+            let dir = parse_dir(chars.next()?).filter(|_| chars.next().is_none())?;
+            let player = match fields.next() {
+                Some(player) => player.parse().ok()?,
+                None => 0,
+            };
+            Input::Turn(player, dir)
+        }
+    };
+    fields.next().is_none().then_some(input)
+}
+
+fn parse_board(text: &str) -> Option<GameConfig> {
+    let mut fields = text.split(' ');
+    let mut config = GameConfig {
+        cols: fields.next()?.parse().ok().filter(|n| *n > 0)?,
+        rows: fields.next()?.parse().ok().filter(|n| *n > 0)?,
+        snake_init_size: fields.next()?.parse().ok().filter(|n| *n > 0)?,
+        ..Default::default()
+    };
+    if let Some(edges) = fields.next() {
+        config.edges = edges.parse().ok()?;
+    }
+    if let Some(players) = fields.next() {
+        config.players = players.parse().ok().filter(|n| *n > 0)?;
+    }
+    fields.next().is_none().then_some(config)
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", REPLAY_MAGIC, REPLAY_VERSION)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(
+            f,
+            "board {} {} {} {} {}",
+            self.config.cols,
+            self.config.rows,
+            self.config.snake_init_size,
+            self.config.edges,
+            self.config.players
+        )?;
+        if let Some(level) = &self.config.level {
+            writeln!(f, "level {}", level.rows)?;
+            write!(f, "{}", level)?;
+        }
+        for event in &self.events {
+            writeln!(f, "{} {}", event.tick, input_code(event.input))?;
+        }
+        writeln!(f, "end {}", self.end_tick)
+    }
+}
+
+impl std::str::FromStr for Replay {
+    type Err = ReplayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+
+        let parse_error = |line: usize, message: &str| ReplayError::Parse {
+            line,
+            message: message.to_string(),
+        };
+
+        let (_, header) = lines.next().ok_or(ReplayError::MissingHeader)?;
+        let version = match header.split_once(' ') {
+            Some((REPLAY_MAGIC, version)) => version
+                .parse::<u32>()
+                .map_err(|_| parse_error(1, "invalid version"))?,
+            _ => return Err(ReplayError::MissingHeader),
+        };
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+
+        let (line, seed) = lines.next().ok_or(parse_error(2, "missing seed"))?;
+        let seed = match seed.split_once(' ') {
+            Some(("seed", seed)) => seed
+                .parse()
+                .map_err(|_| parse_error(line, "invalid seed"))?,
+            _ => return Err(parse_error(line, "expected `seed <number>`")),
+        };
+
+        let mut replay = Replay::new(GameConfig::default(), seed);
+        while let Some((line, text)) = lines.next() {
+            let (first, second) = text
+                .split_once(' ')
+                .ok_or(parse_error(line, "expected `<tick> <input>`"))?;
+            if first == "board" {
+                replay.config = parse_board(second).ok_or(parse_error(
+                    line,
+                    "expected `board <cols> <rows> <length> [wrap|solid] [players]`",
+                ))?;
+                replay
+                    .config
+                    .validate()
+                    .map_err(|err| parse_error(line, &err.to_string()))?;
+                continue;
+            }
+            if first == "level" {
+                let rows = second
+                    .parse()
+                    .map_err(|_| parse_error(line, "invalid level height"))?;
+                let grid: Vec<&str> = lines.by_ref().take(rows).map(|(_, row)| row).collect();
+                let level = grid
+                    .join("\n")
+                    .parse::<Level>()
+                    .map_err(|err| ReplayError::Parse {
+                        line: line + err.line,
+                        message: err.message,
+                    })?;
+                replay.config = replay.config.with_level(level);
+                replay
+                    .config
+                    .validate()
+                    .map_err(|err| parse_error(line, &err.to_string()))?;
+                continue;
+            }
+            if first == "end" {
+                replay.end_tick = second
+                    .parse()
+                    .map_err(|_| parse_error(line, "invalid end tick"))?;
+                return Ok(replay);
+            }
+            let tick = first
+                .parse()
+                .map_err(|_| parse_error(line, "invalid tick"))?;
+            let input = parse_input(second).ok_or(parse_error(line, "unknown input"))?;
+            replay.record(tick, input);
+        }
+
+        Err(parse_error(s.lines().count(), "missing `end <tick>`"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A two-player round with a few turns and a pause, and the replay of it.
+    fn recorded_round() -> (Simulation, Replay) {
+        let config = GameConfig {
+            players: 2,
+            ..GameConfig::default()
+        };
+        let mut sim = Simulation::new(config.clone(), 42);
+        let mut replay = Replay::new(config, 42);
+        for (steps, input) in [
+            (3, Input::Turn(0, Direction::Down)),
+            (2, Input::Turn(1, Direction::Up)),
+            (4, Input::TogglePause),
+            (0, Input::TogglePause),
+            (5, Input::Turn(0, Direction::Left)),
+        ] {
+            for _ in 0..steps {
+                sim.step();
+            }
+            replay.record(sim.tick(), input);
+            sim.apply_input(input);
+        }
+        for _ in 0..20 {
+            sim.step();
+        }
+        replay.end_tick = sim.tick();
+        (sim, replay)
+    }
+
+    #[test]
+    fn recorded_round_plays_back_the_same() {
+        let (sim, replay) = recorded_round();
+        let text = replay.to_string();
+        let parsed: Replay = text.parse().unwrap();
+        assert_eq!(parsed, replay);
+        assert_eq!(
+            parsed.verify(),
+            Ok(ReplaySummary {
+                seed: 42,
+                score: sim.score(),
+                tick: sim.tick(),
+                state: sim.state(),
+            })
+        );
+    }
+
+    #[test]
+    fn bad_replays_are_reported() {
+        let (_, replay) = recorded_round();
+        let text = replay.to_string();
+        assert_eq!("".parse::<Replay>(), Err(ReplayError::MissingHeader));
+        assert_eq!(
+            text.replace("snake-replay 1", "snake-replay 9")
+                .parse::<Replay>(),
+            Err(ReplayError::UnsupportedVersion(9))
+        );
+        assert!(matches!(
+            text.replace("snake-replay 1", "snake-replay x")
+                .parse::<Replay>(),
+            Err(ReplayError::Parse { line: 1, .. })
+        ));
+        // The header, the seed and the board come before the inputs.
+        assert!(matches!(
+            text.replace("5 U 1", "5 Q 1").parse::<Replay>(),
+            Err(ReplayError::Parse { line: 5, .. })
+        ));
+
+        assert!(matches!(
+            text.replace("board 16 9", "board 1 9").parse::<Replay>(),
+            Err(ReplayError::Parse { line: 3, .. })
+        ));
+
+        // A pause out of place stops the round short of its end.
+        let mut changed = replay.clone();
+        changed.events[3].tick += 1;
+        assert!(matches!(changed.verify(), Err(ReplayError::Desync { .. })));
+    }
+}
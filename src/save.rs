@@ -0,0 +1,411 @@
+//! Saving a round in progress and picking it up again.
+//!
+//! Unlike a replay, a save holds the state itself rather than the inputs
+//! that led to it, down to the step timer and the random number generator,
+//! so a restored round goes on exactly as the saved one would have. The text
+//! form looks like this:
+//!
+//! ```text
+//! snake-save 1
+//! board 1p-wrap-classic-16x9
+//! state pause
+//! tick 57
+//! timer 0.041 0.125
+//! seed 1234 5678
+//! time 7.3
+//! player R 4 1 UR 2,4 3,4 4,4 5,4
+//! food 9,2 normal - 0
+//! food 3,7 golden 12 0
+//! speed 0.5 23
+//! ```
+//!
+//! `board` names the board the round was played on, see
+//! [`board_key`](crate::scores::board_key). `timer` is the time left until
+//! the next step and the length of that step, `seed` the seed of the round and
+//! the state of its random number generator, and `time` the seconds played.
+//! A `player` line holds the direction, the score, whether the snake is alive,
+//! the queued turns (`-` for none) and the cells from the tail to the head. A
+//! `food` line holds the cell, the kind, the steps left (`-` for ever) and
+//! whether it just appeared. `speed` is the active speed effect, if any.
+
+use std::fmt;
+
+use crate::{
+    food::{Food, FoodKind},
+    replay::{dir_code, parse_dir},
+    simulation::{Cell, Direction, SpeedEffect, State},
+};
+
+pub const SAVE_VERSION: u32 = 1;
+const SAVE_MAGIC: &str = "snake-save";
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct SavedPlayer {
+    /// From the tail to the head.
+    pub cells: Vec<Cell>,
+    pub dir: Direction,
+    pub next_dirs: Vec<Direction>,
+    pub score: u32,
+    pub alive: bool,
+}
+
+/// Everything needed to carry on with a round.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SavedGame {
+    pub board: String,
+    pub state: State,
+    pub tick: u64,
+    pub step_cooldown: f32,
+    pub step_interval: f32,
+    pub seed: u64,
+    pub rng_state: u64,
+    /// Seconds the round has been played.
+    pub time: f32,
+    pub players: Vec<SavedPlayer>,
+    pub foods: Vec<Food>,
+    pub speed_effect: Option<SpeedEffect>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SaveError {
+    MissingHeader,
+    UnsupportedVersion(u32),
+    Parse {
+        line: usize,
+        message: String,
+    },
+    /// The save is of another board than the one it is restored on.
+    OtherBoard(String),
+    Invalid(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::MissingHeader => write!(f, "missing `{}` header", SAVE_MAGIC),
+            SaveError::UnsupportedVersion(version) => {
+                write!(f, "unsupported save version {}", version)
+            }
+            SaveError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            SaveError::OtherBoard(board) => write!(f, "saved on another board, `{}`", board),
+            SaveError::Invalid(message) => write!(f, "invalid save: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+pub(crate) fn state_name(state: State) -> &'static str {
+    match state {
+        State::GamePlay => "play",
+        State::Pause => "pause",
+        State::GameOver => "over",
+    }
+}
+
+fn parse_state(name: &str) -> Option<State> {
+    match name {
+        "play" => Some(State::GamePlay),
+        "pause" => Some(State::Pause),
+        "over" => Some(State::GameOver),
+        _ => None,
+    }
+}
+
+fn parse_cell(text: &str) -> Option<Cell> {
+    let (x, y) = text.split_once(',')?;
+    Some(Cell {
+        x: x.parse().ok()?,
+        y: y.parse().ok()?,
+    })
+}
+
+fn parse_flag(text: &str) -> Option<bool> {
+    match text {
+        "0" => Some(false),
+        "1" => Some(true),
+        _ => None,
+    }
+}
+
+fn parse_player(text: &str) -> Option<SavedPlayer> {
+    let mut fields = text.split(' ');
+    let mut dir = fields.next()?.chars();
+    let player = SavedPlayer {
+        dir: parse_dir(dir.next()?).filter(|_| dir.next().is_none())?,
+        score: fields.next()?.parse().ok()?,
+        alive: parse_flag(fields.next()?)?,
+        next_dirs: match fields.next()? {
+            "-" => Vec::new(),
+            dirs => dirs.chars().map(parse_dir).collect::<Option<_>>()?,
+        },
+        cells: fields.map(parse_cell).collect::<Option<_>>()?,
+    };
+    (!player.cells.is_empty()).then_some(player)
+}
+
+fn parse_food(text: &str) -> Option<Food> {
+    let mut fields = text.split(' ');
+    let cell = parse_cell(fields.next()?)?;
+    let kind = fields.next()?;
+    let food = Food {
+        cell,
+        kind: FoodKind::ALL
+            .into_iter()
+            .find(|food_kind| food_kind.name() == kind)?,
+        ttl: match fields.next()? {
+            "-" => None,
+            ttl => Some(ttl.parse().ok()?),
+        },
+        fresh: parse_flag(fields.next()?)?,
+    };
+    fields.next().is_none().then_some(food)
+}
+
+/// Two numbers separated by a space.
+fn parse_pair<A: std::str::FromStr, B: std::str::FromStr>(text: &str) -> Option<(A, B)> {
+    let (a, b) = text.split_once(' ')?;
+    Some((a.parse().ok()?, b.parse().ok()?))
+}
+
+impl fmt::Display for SavedGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", SAVE_MAGIC, SAVE_VERSION)?;
+        writeln!(f, "board {}", self.board)?;
+        writeln!(f, "state {}", state_name(self.state))?;
+        writeln!(f, "tick {}", self.tick)?;
+        writeln!(f, "timer {} {}", self.step_cooldown, self.step_interval)?;
+        writeln!(f, "seed {} {}", self.seed, self.rng_state)?;
+        writeln!(f, "time {}", self.time)?;
+        for player in &self.players {
+            let next_dirs: String = player.next_dirs.iter().map(|dir| dir_code(*dir)).collect();
+            write!(
+                f,
+                "player {} {} {} {}",
+                dir_code(player.dir),
+                player.score,
+                player.alive as u8,
+                if next_dirs.is_empty() {
+                    "-"
+                } else {
+                    &next_dirs
+                }
+            )?;
+            for cell in &player.cells {
+                write!(f, " {},{}", cell.x, cell.y)?;
+            }
+            writeln!(f)?;
+        }
+        for food in &self.foods {
+            write!(
+                f,
+                "food {},{} {} ",
+                food.cell.x,
+                food.cell.y,
+                food.kind.name()
+            )?;
+            match food.ttl {
+                Some(ttl) => write!(f, "{}", ttl)?,
+                None => write!(f, "-")?,
+            }
+            writeln!(f, " {}", food.fresh as u8)?;
+        }
+        if let Some(effect) = self.speed_effect {
+            writeln!(f, "speed {} {}", effect.factor, effect.steps_left)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for SavedGame {
+    type Err = SaveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+
+        let parse_error = |line: usize, message: &str| SaveError::Parse {
+            line,
+            message: message.to_string(),
+        };
+
+        let (_, header) = lines.next().ok_or(SaveError::MissingHeader)?;
+        let version = match header.split_once(' ') {
+            Some((SAVE_MAGIC, version)) => version
+                .parse::<u32>()
+                .map_err(|_| parse_error(1, "invalid version"))?,
+            _ => return Err(SaveError::MissingHeader),
+        };
+        if version != SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(version));
+        }
+
+        let mut board = None;
+        let mut state = None;
+        let mut tick = None;
+        let mut timer = None;
+        let mut seed = None;
+        let mut time = 0.0;
+        let mut players = Vec::new();
+        let mut foods = Vec::new();
+        let mut speed_effect = None;
+        for (line, text) in lines {
+            let (name, value) = text
+                .split_once(' ')
+                .ok_or(parse_error(line, "expected `<name> <value>`"))?;
+            let invalid = || parse_error(line, &format!("invalid `{}`", name));
+            match name {
+                "board" => board = Some(value.to_string()),
+                "state" => state = Some(parse_state(value).ok_or_else(invalid)?),
+                "tick" => tick = Some(value.parse().map_err(|_| invalid())?),
+                "timer" => timer = Some(parse_pair(value).ok_or_else(invalid)?),
+                "seed" => seed = Some(parse_pair(value).ok_or_else(invalid)?),
+                "time" => time = value.parse().map_err(|_| invalid())?,
+                "player" => players.push(parse_player(value).ok_or_else(invalid)?),
+                "food" => foods.push(parse_food(value).ok_or_else(invalid)?),
+                "speed" => {
+                    let (factor, steps_left) = parse_pair(value).ok_or_else(invalid)?;
+                    speed_effect = Some(SpeedEffect { factor, steps_left });
+                }
+                _ => return Err(parse_error(line, &format!("unknown `{}`", name))),
+            }
+        }
+
+        let lines = s.lines().count();
+        let missing = |name: &str| parse_error(lines, &format!("missing `{}`", name));
+        let (step_cooldown, step_interval) = timer.ok_or_else(|| missing("timer"))?;
+        let (seed, rng_state) = seed.ok_or_else(|| missing("seed"))?;
+        if players.is_empty() {
+            return Err(missing("player"));
+        }
+        Ok(SavedGame {
+            board: board.ok_or_else(|| missing("board"))?,
+            state: state.ok_or_else(|| missing("state"))?,
+            tick: tick.ok_or_else(|| missing("tick"))?,
+            step_cooldown,
+            step_interval,
+            seed,
+            rng_state,
+            time,
+            players,
+            foods,
+            speed_effect,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::GameConfig,
+        simulation::{Input, Simulation},
+    };
+
+    #[test]
+    fn restored_round_goes_on_the_same() {
+        let config = GameConfig {
+            players: 2,
+            ..GameConfig::default()
+        };
+        let mut sim = Simulation::new(config.clone(), 7);
+        for (steps, dir) in [
+            (3, Direction::Down),
+            (6, Direction::Left),
+            (4, Direction::Up),
+        ] {
+            for _ in 0..steps {
+                sim.step();
+            }
+            sim.apply_input(Input::Turn(0, dir));
+            sim.apply_input(Input::Turn(1, !dir));
+        }
+
+        let text = sim.to_saved().to_string();
+        let saved: SavedGame = text.parse().unwrap();
+        assert_eq!(saved, sim.to_saved());
+        let mut restored = Simulation::from_saved(config, &saved).unwrap();
+        for _ in 0..200 {
+            sim.step();
+            restored.step();
+        }
+        assert_eq!(restored.to_saved(), sim.to_saved());
+    }
+
+    #[test]
+    fn bad_saves_are_refused() {
+        let sim = Simulation::new(GameConfig::default(), 7);
+        let text = sim.to_saved().to_string();
+        assert_eq!("".parse::<SavedGame>(), Err(SaveError::MissingHeader));
+        assert_eq!(
+            text.replace("snake-save 1", "snake-save 9")
+                .parse::<SavedGame>(),
+            Err(SaveError::UnsupportedVersion(9))
+        );
+        assert!(matches!(
+            text.replace("state play", "state lost")
+                .parse::<SavedGame>(),
+            Err(SaveError::Parse { line: 3, .. })
+        ));
+
+        let saved: SavedGame = text.parse().unwrap();
+        let config = GameConfig {
+            cols: 20,
+            ..GameConfig::default()
+        };
+        assert_eq!(
+            Simulation::from_saved(config, &saved).err(),
+            Some(SaveError::OtherBoard(saved.board.clone()))
+        );
+
+        let invalid = |config: &GameConfig, change: &dyn Fn(&mut SavedGame)| {
+            let mut saved = Simulation::new(config.clone(), 7).to_saved();
+            change(&mut saved);
+            match Simulation::from_saved(config.clone(), &saved) {
+                Err(SaveError::Invalid(message)) => message,
+                Err(err) => panic!("{}", err),
+                Ok(_) => panic!("restored"),
+            }
+        };
+        let config = GameConfig::default();
+        let at = |x, y| Cell { x, y };
+        assert_eq!(
+            invalid(&config, &|saved| saved.players[0].cells.truncate(1)),
+            "snake is shorter than 2 cells"
+        );
+        assert_eq!(
+            invalid(&config, &|saved| saved.players[0].cells[0] = at(0, 6)),
+            "snake cells 0,6 and 1,4 are apart"
+        );
+        assert_eq!(
+            invalid(&config, &|saved| saved.step_cooldown = f32::NAN),
+            "timer is not a number"
+        );
+        assert_eq!(
+            invalid(&config, &|saved| saved.time = f32::INFINITY),
+            "timer is not a number"
+        );
+        assert_eq!(
+            invalid(&config, &|saved| {
+                saved.speed_effect = Some(SpeedEffect {
+                    factor: f32::NAN,
+                    steps_left: 3,
+                })
+            }),
+            "speed factor is not positive"
+        );
+        let level = GameConfig::default().with_level(crate::level::Level::builtin("box").unwrap());
+        assert_eq!(
+            invalid(&level, &|saved| saved.players[0].cells[0] = at(0, 0)),
+            "snake on the wall at 0,0"
+        );
+
+        // Across the edge is next to each other on a board that wraps around.
+        let mut saved = Simulation::new(config.clone(), 7).to_saved();
+        saved.players[0].cells = vec![at(15, 4), at(0, 4), at(1, 4)];
+        assert!(Simulation::from_saved(config, &saved).is_ok());
+    }
+}
@@ -0,0 +1,188 @@
+//! High scores, one table per board.
+//!
+//! A table is stored as text, best entry first, one entry per line:
+//!
+//! ```text
+//! # score length seconds seed name
+//! 42 45 93.5 7311296315185916312 Player 1
+//! ```
+
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{
+    config::GameConfig,
+    settings::{LocalStorageSettings, SettingsStore},
+};
+
+// Constants
+/// Entries kept per board.
+pub const MAX_SCORES: usize = 10;
+const COMMENT: char = '#';
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ScoreEntry {
+    pub name: String,
+    pub score: u32,
+    /// Length of the snake when the round ended.
+    pub length: usize,
+    /// Seconds of play, pauses left out.
+    pub duration: f32,
+    /// Seed of the round, to play it again.
+    pub seed: u64,
+}
+
+/// The table a game goes in: the number of players, the edges, the difficulty,
+/// the board size and the level, e.g. `1p-wrap-classic-16x9`. Custom levels
+/// share one table.
+pub fn board_key(config: &GameConfig) -> String {
+    let mut key = format!(
+        "{}p-{}-{}-{}x{}",
+        config.players, config.edges, config.difficulty, config.cols, config.rows
+    );
+    if let Some(level) = &config.level {
+        write!(key, "-{}", level.builtin_name().unwrap_or("custom")).unwrap();
+    }
+    key
+}
+
+pub fn format_scores(scores: &[ScoreEntry]) -> String {
+    let mut text = String::new();
+    for entry in scores {
+        writeln!(
+            text,
+            "{} {} {} {} {}",
+            entry.score, entry.length, entry.duration, entry.seed, entry.name
+        )
+        .unwrap();
+    }
+    text
+}
+
+/// Reads a table, skipping lines that do not parse rather than losing the
+/// whole table to one bad line.
+pub fn parse_scores(text: &str) -> Vec<ScoreEntry> {
+    let parse_line = |line: &str| {
+        let mut fields = line.splitn(5, ' ');
+        Some(ScoreEntry {
+            score: fields.next()?.parse().ok()?,
+            length: fields.next()?.parse().ok()?,
+            duration: fields.next()?.parse().ok()?,
+            seed: fields.next()?.parse().ok()?,
+            name: fields.next()?.to_string(),
+        })
+    };
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(COMMENT))
+        .filter_map(parse_line)
+        .collect()
+}
+
+/// Where the high-score tables are kept.
+pub trait ScoreStore {
+    /// The table of `board`, best entry first.
+    fn scores(&self, board: &str) -> Vec<ScoreEntry>;
+
+    fn set_scores(&mut self, board: &str, scores: &[ScoreEntry]);
+
+    /// Puts `entry` in the table of `board` if it makes the top
+    /// [`MAX_SCORES`], below older entries with the same score, and tells
+    /// its rank from 0.
+    fn add(&mut self, board: &str, entry: ScoreEntry) -> Option<usize> {
+        let mut scores = self.scores(board);
+        let rank = scores
+            .iter()
+            .position(|other| other.score < entry.score)
+            .unwrap_or(scores.len());
+        if rank >= MAX_SCORES {
+            return None;
+        }
+        scores.insert(rank, entry);
+        scores.truncate(MAX_SCORES);
+        self.set_scores(board, &scores);
+        Some(rank)
+    }
+}
+
+/// Tables that last as long as the store, for tests and the terminal.
+#[derive(Default)]
+pub struct MemoryScores {
+    tables: HashMap<String, Vec<ScoreEntry>>,
+}
+
+impl ScoreStore for MemoryScores {
+    fn scores(&self, board: &str) -> Vec<ScoreEntry> {
+        self.tables.get(board).cloned().unwrap_or_default()
+    }
+
+    fn set_scores(&mut self, board: &str, scores: &[ScoreEntry]) {
+        self.tables.insert(board.to_string(), scores.to_vec());
+    }
+}
+
+/// Tables in the browser's `localStorage`, one item per board.
+pub struct LocalStorageScores {
+    settings: LocalStorageSettings,
+}
+
+impl LocalStorageScores {
+    /// `None` when the browser has no `localStorage` or it is disabled.
+    pub fn new() -> Option<Self> {
+        Some(Self {
+            settings: LocalStorageSettings::new()?,
+        })
+    }
+}
+
+impl ScoreStore for LocalStorageScores {
+    fn scores(&self, board: &str) -> Vec<ScoreEntry> {
+        self.settings
+            .load(&format!("scores.{}", board))
+            .map(|text| parse_scores(&text))
+            .unwrap_or_default()
+    }
+
+    fn set_scores(&mut self, board: &str, scores: &[ScoreEntry]) {
+        self.settings
+            .save(&format!("scores.{}", board), &format_scores(scores));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: u32) -> ScoreEntry {
+        ScoreEntry {
+            name: name.to_string(),
+            score,
+            length: 3 + score as usize,
+            duration: 12.5,
+            seed: 42,
+        }
+    }
+
+    #[test]
+    fn table_keeps_the_best_in_order() {
+        let mut store = MemoryScores::default();
+        for i in 0..MAX_SCORES as u32 {
+            assert!(store.add("board", entry("a", i * 10)).is_some());
+        }
+        assert_eq!(store.add("board", entry("b", 0)), None);
+        assert_eq!(store.add("board", entry("c", 40)), Some(6));
+        assert_eq!(store.add("other", entry("d", 1)), Some(0));
+
+        let scores = store.scores("board");
+        assert_eq!(scores.len(), MAX_SCORES);
+        assert_eq!(scores[0].score, 90);
+        assert_eq!(scores[6].name, "c");
+        assert!(scores.windows(2).all(|w| w[0].score >= w[1].score));
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let scores = vec![entry("Player 1", 7), entry("Ana", 2)];
+        let text = format!("# comment\n{}bad line\n", format_scores(&scores));
+        assert_eq!(parse_scores(&text), scores);
+    }
+}
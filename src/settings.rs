@@ -0,0 +1,67 @@
+//! Where settings such as the keymap are kept between runs.
+
+use std::collections::HashMap;
+
+/// A store of named settings, each one a string in its own text format.
+pub trait SettingsStore {
+    fn load(&self, name: &str) -> Option<String>;
+    fn save(&mut self, name: &str, value: &str);
+    fn remove(&mut self, name: &str);
+}
+
+/// Settings that last as long as the store, for tests and frontends without storage.
+#[derive(Default)]
+pub struct MemorySettings {
+    values: HashMap<String, String>,
+}
+
+impl SettingsStore for MemorySettings {
+    fn load(&self, name: &str) -> Option<String> {
+        self.values.get(name).cloned()
+    }
+
+    fn save(&mut self, name: &str, value: &str) {
+        self.values.insert(name.to_string(), value.to_string());
+    }
+
+    fn remove(&mut self, name: &str) {
+        self.values.remove(name);
+    }
+}
+
+/// Settings in the browser's `localStorage`, with every name prefixed so they
+/// stay apart from other pages of the same origin.
+pub struct LocalStorageSettings {
+    storage: web_sys::Storage,
+}
+
+const LOCAL_STORAGE_PREFIX: &str = "snake.";
+
+impl LocalStorageSettings {
+    /// `None` when the browser has no `localStorage` or it is disabled.
+    pub fn new() -> Option<Self> {
+        let storage = web_sys::window()?.local_storage().ok()??;
+        Some(Self { storage })
+    }
+}
+
+impl SettingsStore for LocalStorageSettings {
+    fn load(&self, name: &str) -> Option<String> {
+        self.storage
+            .get_item(&format!("{}{}", LOCAL_STORAGE_PREFIX, name))
+            .ok()?
+    }
+
+    /// A full or blocked storage loses the setting, which is not worth stopping the game for.
+    fn save(&mut self, name: &str, value: &str) {
+        let _ = self
+            .storage
+            .set_item(&format!("{}{}", LOCAL_STORAGE_PREFIX, name), value);
+    }
+
+    fn remove(&mut self, name: &str) {
+        let _ = self
+            .storage
+            .remove_item(&format!("{}{}", LOCAL_STORAGE_PREFIX, name));
+    }
+}
@@ -0,0 +1,1022 @@
+use std::collections::VecDeque;
+
+use crate::{
+    config::{Edges, GameConfig},
+    food::{Food, FoodKind, SPEED_EFFECT_STEPS},
+    save::{SaveError, SavedGame, SavedPlayer},
+    scores::board_key,
+    utils::{Rng, emod, ring_displace_back},
+};
+
+// Constants
+const RANDOM_FOOD_MAX_ATTEMPTS: u32 = 1000;
+const DIR_QUEUE_CAP: usize = 3;
+pub const SNAKE_MIN_SIZE: usize = 2;
+const MAX_BONUS_FOODS: usize = 2;
+const BONUS_FOOD_CHANCE: u32 = 4;
+pub const MAX_PLAYERS: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    Right = 0,
+    Up = 1,
+    Left = 2,
+    Down = 3,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Right,
+        Direction::Up,
+        Direction::Left,
+        Direction::Down,
+    ];
+}
+
+impl From<Direction> for Cell {
+    fn from(dir: Direction) -> Self {
+        match dir {
+            Direction::Right => Cell { x: 1, y: 0 },
+            Direction::Left => Cell { x: -1, y: 0 },
+            Direction::Up => Cell { x: 0, y: -1 },
+            Direction::Down => Cell { x: 0, y: 1 },
+        }
+    }
+}
+
+impl std::ops::Not for Direction {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        match self {
+            Direction::Right => Direction::Left,
+            Direction::Up => Direction::Down,
+            Direction::Left => Direction::Right,
+            Direction::Down => Direction::Up,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum State {
+    GamePlay,
+    Pause,
+    GameOver,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Vec2<I> {
+    pub x: I,
+    pub y: I,
+}
+
+impl<I> std::ops::Sub for Vec2<I>
+where
+    I: std::ops::Sub<Output = I>,
+{
+    type Output = Vec2<I>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Vec2 {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
+impl<I> std::ops::Add for Vec2<I>
+where
+    I: std::ops::Add<Output = I>,
+{
+    type Output = Vec2<I>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Vec2 {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
+pub type Cell = Vec2<i32>;
+
+impl Cell {
+    /// Finds the direction from this cell to an adjacent one, looking across
+    /// the board edges only when the cells are not next to each other directly.
+    pub fn determine_dir(&self, another: &Cell, config: &GameConfig) -> Direction {
+        for dir in Direction::ALL {
+            if *self + dir.into() == *another {
+                return dir;
+            }
+        }
+        for dir in Direction::ALL {
+            let mut res: Cell = *self + dir.into();
+            res.wrap_by_game_size(config);
+            if res == *another {
+                return dir;
+            }
+        }
+        unreachable!()
+    }
+
+    /// Whether a snake can go from this cell to `another` in one step.
+    pub fn is_next_to(&self, another: &Cell, config: &GameConfig) -> bool {
+        Direction::ALL
+            .into_iter()
+            .any(|dir| self.advance(dir, config) == *another)
+    }
+
+    pub fn advance(&self, dir: Direction, config: &GameConfig) -> Cell {
+        let dir_cell: Cell = dir.into();
+        let mut res: Cell = dir_cell + *self;
+        if config.edges == Edges::Wrap {
+            res.wrap_by_game_size(config);
+        }
+        res
+    }
+
+    fn wrap_by_game_size(&mut self, config: &GameConfig) {
+        self.x = emod(self.x, config.cols);
+        self.y = emod(self.y, config.rows);
+    }
+}
+
+pub struct Snake {
+    pub items: VecDeque<Cell>,
+}
+
+impl Snake {
+    pub fn contains_cell(&self, cell: &Cell) -> bool {
+        self.items.contains(cell)
+    }
+
+    pub fn size(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn head(&self) -> &Cell {
+        self.items.back().unwrap()
+    }
+
+    pub fn tail(&self) -> &Cell {
+        self.items.front().unwrap()
+    }
+}
+
+/// One snake on the board together with its queued turns and score.
+pub struct Player {
+    snake: Snake,
+    dir: Direction,
+    next_dirs: VecDeque<Direction>,
+    score: u32,
+    alive: bool,
+}
+
+impl Player {
+    fn new() -> Self {
+        Self {
+            snake: Snake {
+                items: VecDeque::new(),
+            },
+            dir: Direction::Right,
+            next_dirs: VecDeque::with_capacity(DIR_QUEUE_CAP),
+            score: 0,
+            alive: true,
+        }
+    }
+
+    pub fn snake(&self) -> &Snake {
+        &self.snake
+    }
+
+    pub fn dir(&self) -> Direction {
+        self.dir
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.alive
+    }
+}
+
+/// A player intent fed into the simulation, independent of where it came from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Input {
+    /// Queues a turn for the player with the given index.
+    Turn(usize, Direction),
+    TogglePause,
+    Restart,
+}
+
+/// What happened to one player during a simulation step.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StepEvent {
+    Moved(usize),
+    Ate(usize, FoodKind),
+    Died(usize, Cell),
+}
+
+/// A temporary change of speed caused by eating a food.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SpeedEffect {
+    pub factor: f32,
+    pub steps_left: u32,
+}
+
+/// The rules of the game with no notion of pixels, time sources or platforms.
+///
+/// All players move at once. A player dies when its head leaves a solid
+/// board, hits a wall or any living snake (its own included), or lands on
+/// the same cell as another head, in which case both die. With one player
+/// the round ends when it dies, otherwise when at most one is left alive.
+pub struct Simulation {
+    config: GameConfig,
+
+    state: State,
+    step_cooldown: f32,
+    step_interval: f32,
+    tick: u64,
+
+    players: Vec<Player>,
+    foods: Vec<Food>,
+    speed_effect: Option<SpeedEffect>,
+
+    seed: u64,
+    rng: Rng,
+}
+
+impl Simulation {
+    pub fn new(config: GameConfig, seed: u64) -> Self {
+        let mut sim = Self {
+            state: State::GamePlay,
+            step_cooldown: 0.0,
+            step_interval: config.step_interval,
+            tick: 0,
+            players: Vec::new(),
+            foods: Vec::new(),
+            speed_effect: None,
+            seed,
+            rng: Rng::new(seed),
+            config,
+        };
+        sim.restart_with_seed(seed);
+        sim
+    }
+
+    /// Starts a new round whose seed is drawn from the current one, so a whole
+    /// session stays reproducible from its first seed.
+    pub fn restart(&mut self) {
+        let seed = self.rng.next_u64();
+        self.restart_with_seed(seed);
+    }
+
+    pub fn restart_with_config(&mut self, config: GameConfig, seed: u64) {
+        self.config = config;
+        self.restart_with_seed(seed);
+    }
+
+    pub fn restart_with_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = Rng::new(seed);
+        self.state = State::GamePlay;
+        self.step_cooldown = 0.0;
+        self.tick = 0;
+        self.foods.clear();
+        self.speed_effect = None;
+
+        self.players.clear();
+        for i in 0..self.config.players.clamp(1, MAX_PLAYERS) {
+            let player = self.spawn_player(i);
+            self.players.push(player);
+        }
+
+        self.step_interval = self.next_step_interval();
+        self.spawn_food(FoodKind::Normal, true);
+    }
+
+    fn spawn_player(&self, i: usize) -> Player {
+        let snake_init_size = self
+            .config
+            .snake_init_size
+            .clamp(SNAKE_MIN_SIZE, self.config.cols as usize);
+
+        let (mut head, dir) = match &self.config.level {
+            Some(level) if i == 0 => (level.start, level.start_dir),
+            Some(level) if i == 1 => (
+                Cell {
+                    x: level.cols - 1 - level.start.x,
+                    y: level.rows - 1 - level.start.y,
+                },
+                !level.start_dir,
+            ),
+            _ if i.is_multiple_of(2) => (
+                Cell {
+                    x: snake_init_size as i32 - 1,
+                    y: self.config.snake_init_row(i),
+                },
+                Direction::Right,
+            ),
+            _ => (
+                Cell {
+                    x: self.config.cols - snake_init_size as i32,
+                    y: self.config.snake_init_row(i),
+                },
+                Direction::Left,
+            ),
+        };
+        if !self.is_free(&head)
+            && let Some(cell) = self.first_free_cell()
+        {
+            head = cell;
+        }
+
+        let mut player = Player::new();
+        player.dir = dir;
+        player
+            .snake
+            .items
+            .reserve((self.config.cols * self.config.rows) as usize);
+        player.snake.items.push_back(head);
+        while player.snake.size() < snake_init_size {
+            let tail = player.snake.tail().advance(!dir, &self.config);
+            if !self.is_free(&tail) || player.snake.contains_cell(&tail) {
+                break;
+            }
+            player.snake.items.push_front(tail);
+        }
+        player
+    }
+
+    pub fn apply_input(&mut self, input: Input) {
+        match (self.state, input) {
+            (_, Input::Restart) => self.restart(),
+            (State::GamePlay, Input::Turn(player, dir)) => {
+                if let Some(player) = self.players.get_mut(player)
+                    && player.alive
+                {
+                    ring_displace_back(&mut player.next_dirs, dir, DIR_QUEUE_CAP);
+                }
+            }
+            (State::GamePlay, Input::TogglePause) => self.state = State::Pause,
+            (State::Pause, Input::TogglePause) => self.state = State::GamePlay,
+            _ => {}
+        }
+    }
+
+    /// Advances the step timer by `dt` seconds and performs a step once it runs out.
+    pub fn update(&mut self, dt: f32) -> Option<Vec<StepEvent>> {
+        if !self.advance_timer(dt) {
+            return None;
+        }
+
+        self.step()
+    }
+
+    /// Advances the step timer by `dt` seconds and tells whether a step is due,
+    /// leaving room to feed inputs in right before [`Simulation::step`].
+    pub fn advance_timer(&mut self, dt: f32) -> bool {
+        if self.state != State::GamePlay {
+            return false;
+        }
+
+        self.step_cooldown -= dt;
+        self.step_due()
+    }
+
+    /// Whether the step timer has run out. Time past it counts towards the
+    /// next step, so a long `dt` can leave several steps due.
+    pub fn step_due(&self) -> bool {
+        self.state == State::GamePlay && self.step_cooldown <= 0.0
+    }
+
+    /// Forgets the steps that are overdue, keeping how far into the current
+    /// one the timer is.
+    pub fn skip_due_steps(&mut self) {
+        if self.step_cooldown <= 0.0 {
+            self.step_cooldown = self.step_cooldown.rem_euclid(self.step_interval);
+        }
+    }
+
+    /// How far the game is from the last step to the next, from 0 right after
+    /// a step to 1 when the next one is due, for drawing in between.
+    pub fn step_alpha(&self) -> f32 {
+        (1.0 - self.step_cooldown / self.step_interval).clamp(0.0, 1.0)
+    }
+
+    /// Performs a single step right away, regardless of the step timer.
+    pub fn step(&mut self) -> Option<Vec<StepEvent>> {
+        if self.state != State::GamePlay {
+            return None;
+        }
+
+        self.tick += 1;
+        self.age_foods();
+
+        let mut next_heads = Vec::with_capacity(self.players.len());
+        for player in &mut self.players {
+            if !player.alive {
+                next_heads.push(None);
+                continue;
+            }
+            if let Some(next_dir) = player.next_dirs.pop_front()
+                && !player.dir != next_dir
+            {
+                player.dir = next_dir;
+            }
+            next_heads.push(Some(player.snake.head().advance(player.dir, &self.config)));
+        }
+
+        let dying: Vec<bool> = next_heads
+            .iter()
+            .enumerate()
+            .map(|(i, next_head)| {
+                next_head.is_some_and(|head| self.collides(i, &head, &next_heads))
+            })
+            .collect();
+
+        let mut events = Vec::with_capacity(self.players.len());
+        let mut eaten_normal = 0;
+        for (i, next_head) in next_heads.into_iter().enumerate() {
+            let Some(next_head) = next_head else {
+                continue;
+            };
+            if dying[i] {
+                self.players[i].alive = false;
+                events.push(StepEvent::Died(i, next_head));
+                continue;
+            }
+
+            match self.foods.iter().position(|food| food.cell == next_head) {
+                Some(f) => {
+                    let kind = self.foods.remove(f).kind;
+                    self.eat(i, next_head, kind);
+                    if kind == FoodKind::Normal {
+                        eaten_normal += 1;
+                    }
+                    events.push(StepEvent::Ate(i, kind));
+                }
+                None => {
+                    let snake = &mut self.players[i].snake;
+                    snake.items.push_back(next_head);
+                    snake.items.pop_front();
+                    events.push(StepEvent::Moved(i));
+                }
+            }
+        }
+
+        // Foods are replaced once every snake has moved so none lands under a head.
+        for _ in 0..eaten_normal {
+            self.replace_normal_food();
+        }
+
+        let alive = self.players.iter().filter(|player| player.alive).count();
+        if alive == 0 || (self.players.len() > 1 && alive == 1) {
+            self.state = State::GameOver;
+        }
+
+        if self.state == State::GameOver {
+            self.step_cooldown = 0.0;
+        } else {
+            self.step_interval = self.next_step_interval();
+            self.step_cooldown = self.step_cooldown.min(0.0) + self.step_interval;
+        }
+        Some(events)
+    }
+
+    fn collides(&self, i: usize, head: &Cell, next_heads: &[Option<Cell>]) -> bool {
+        !self.config.contains(head)
+            || self.is_wall(head)
+            || self
+                .players
+                .iter()
+                .any(|player| player.alive && player.snake.contains_cell(head))
+            || next_heads
+                .iter()
+                .enumerate()
+                .any(|(j, other)| j != i && *other == Some(*head))
+    }
+
+    fn age_foods(&mut self) {
+        for food in &mut self.foods {
+            food.fresh = false;
+            if let Some(ttl) = &mut food.ttl {
+                *ttl = ttl.saturating_sub(1);
+            }
+        }
+        self.foods.retain(|food| food.ttl != Some(0));
+
+        if let Some(effect) = &mut self.speed_effect {
+            effect.steps_left = effect.steps_left.saturating_sub(1);
+            if effect.steps_left == 0 {
+                self.speed_effect = None;
+            }
+        }
+    }
+
+    fn eat(&mut self, i: usize, next_head: Cell, kind: FoodKind) {
+        let player = &mut self.players[i];
+        player.snake.items.push_back(next_head);
+        if kind.growth() <= 0 {
+            player.snake.items.pop_front();
+            for _ in 0..-kind.growth() {
+                if player.snake.size() > SNAKE_MIN_SIZE {
+                    player.snake.items.pop_front();
+                }
+            }
+        }
+
+        player.score += kind.score();
+        if let Some(factor) = kind.speed_factor() {
+            self.speed_effect = Some(SpeedEffect {
+                factor,
+                steps_left: SPEED_EFFECT_STEPS,
+            });
+        }
+    }
+
+    fn replace_normal_food(&mut self) {
+        if !self.spawn_food(FoodKind::Normal, false) {
+            self.state = State::GameOver;
+            return;
+        }
+
+        let bonuses = self
+            .foods
+            .iter()
+            .filter(|food| food.kind != FoodKind::Normal)
+            .count();
+        if bonuses < MAX_BONUS_FOODS && self.rng.next_u32().is_multiple_of(BONUS_FOOD_CHANCE) {
+            let bonus =
+                FoodKind::BONUSES[(self.rng.next_u32() % FoodKind::BONUSES.len() as u32) as usize];
+            self.spawn_food(bonus, false);
+        }
+    }
+
+    fn next_step_interval(&self) -> f32 {
+        let interval = self
+            .config
+            .difficulty
+            .step_interval(self.config.step_interval, self.score());
+        interval * self.speed_effect.map_or(1.0, |effect| effect.factor)
+    }
+
+    /// Length of the step currently being waited on, including difficulty and speed effects.
+    pub fn step_interval(&self) -> f32 {
+        self.step_interval
+    }
+
+    pub fn level(&self) -> u32 {
+        self.config.difficulty.level(self.score())
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn is_wall(&self, cell: &Cell) -> bool {
+        self.config
+            .level
+            .as_ref()
+            .is_some_and(|level| level.is_wall(cell))
+    }
+
+    fn is_free(&self, cell: &Cell) -> bool {
+        self.config.contains(cell)
+            && !self.is_wall(cell)
+            && !self
+                .players
+                .iter()
+                .any(|player| player.alive && player.snake.contains_cell(cell))
+            && !self.foods.iter().any(|food| food.cell == *cell)
+    }
+
+    fn first_free_cell(&self) -> Option<Cell> {
+        (0..self.config.rows)
+            .flat_map(|y| (0..self.config.cols).map(move |x| Cell { x, y }))
+            .find(|cell| self.is_free(cell))
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// The highest score among the players.
+    pub fn score(&self) -> u32 {
+        self.players
+            .iter()
+            .map(|player| player.score)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    pub fn player(&self, i: usize) -> &Player {
+        &self.players[i]
+    }
+
+    /// The last player standing once a round with several players is over,
+    /// `None` while it is running, for a single player or for a draw.
+    pub fn winner(&self) -> Option<usize> {
+        if self.state != State::GameOver || self.players.len() < 2 {
+            return None;
+        }
+        let mut alive = self
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(_, player)| player.alive);
+        match (alive.next(), alive.next()) {
+            (Some((i, _)), None) => Some(i),
+            _ => None,
+        }
+    }
+
+    pub fn foods(&self) -> &[Food] {
+        &self.foods
+    }
+
+    pub fn speed_effect(&self) -> Option<SpeedEffect> {
+        self.speed_effect
+    }
+
+    pub fn step_cooldown(&self) -> f32 {
+        self.step_cooldown
+    }
+
+    /// Number of steps taken since the round started.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The whole state of the round, see [`crate::save`]. The seconds played
+    /// are left at 0 for the caller to fill in.
+    pub fn to_saved(&self) -> SavedGame {
+        SavedGame {
+            board: board_key(&self.config),
+            state: self.state,
+            tick: self.tick,
+            step_cooldown: self.step_cooldown,
+            step_interval: self.step_interval,
+            seed: self.seed,
+            rng_state: self.rng.state(),
+            time: 0.0,
+            players: self
+                .players
+                .iter()
+                .map(|player| SavedPlayer {
+                    cells: player.snake.items.iter().copied().collect(),
+                    dir: player.dir,
+                    next_dirs: player.next_dirs.iter().copied().collect(),
+                    score: player.score,
+                    alive: player.alive,
+                })
+                .collect(),
+            foods: self.foods.clone(),
+            speed_effect: self.speed_effect,
+        }
+    }
+
+    /// Carries on with a saved round on `config`, which has to be the board
+    /// it was saved on.
+    pub fn from_saved(config: GameConfig, saved: &SavedGame) -> Result<Self, SaveError> {
+        if saved.board != board_key(&config) {
+            return Err(SaveError::OtherBoard(saved.board.clone()));
+        }
+        let players = config.players.clamp(1, MAX_PLAYERS);
+        if saved.players.len() != players {
+            return Err(SaveError::Invalid(format!(
+                "{} players on a board for {}",
+                saved.players.len(),
+                players
+            )));
+        }
+        if !saved.step_interval.is_finite() || saved.step_interval <= 0.0 {
+            return Err(SaveError::Invalid(
+                "step interval is not positive".to_string(),
+            ));
+        }
+        if !saved.step_cooldown.is_finite() || !saved.time.is_finite() {
+            return Err(SaveError::Invalid("timer is not a number".to_string()));
+        }
+        if let Some(effect) = saved.speed_effect
+            && (!effect.factor.is_finite() || effect.factor <= 0.0)
+        {
+            return Err(SaveError::Invalid(
+                "speed factor is not positive".to_string(),
+            ));
+        }
+        let outside = saved
+            .players
+            .iter()
+            .flat_map(|player| &player.cells)
+            .chain(saved.foods.iter().map(|food| &food.cell))
+            .find(|cell| !config.contains(cell));
+        if let Some(cell) = outside {
+            return Err(SaveError::Invalid(format!(
+                "cell {},{} is off the board",
+                cell.x, cell.y
+            )));
+        }
+        let on_wall = |cell: &&Cell| {
+            config
+                .level
+                .as_ref()
+                .is_some_and(|level| level.is_wall(cell))
+        };
+        for player in &saved.players {
+            if player.cells.len() < SNAKE_MIN_SIZE {
+                return Err(SaveError::Invalid(format!(
+                    "snake is shorter than {} cells",
+                    SNAKE_MIN_SIZE
+                )));
+            }
+            if let Some(cell) = player.cells.iter().find(on_wall) {
+                return Err(SaveError::Invalid(format!(
+                    "snake on the wall at {},{}",
+                    cell.x, cell.y
+                )));
+            }
+            if let Some(pair) = player
+                .cells
+                .windows(2)
+                .find(|pair| !pair[0].is_next_to(&pair[1], &config))
+            {
+                return Err(SaveError::Invalid(format!(
+                    "snake cells {},{} and {},{} are apart",
+                    pair[0].x, pair[0].y, pair[1].x, pair[1].y
+                )));
+            }
+        }
+        if let Some(food) = saved.foods.iter().find(|food| on_wall(&&food.cell)) {
+            return Err(SaveError::Invalid(format!(
+                "food on the wall at {},{}",
+                food.cell.x, food.cell.y
+            )));
+        }
+
+        Ok(Self {
+            config,
+            state: saved.state,
+            step_cooldown: saved.step_cooldown,
+            step_interval: saved.step_interval,
+            tick: saved.tick,
+            players: saved
+                .players
+                .iter()
+                .map(|player| {
+                    let mut next_dirs = VecDeque::with_capacity(DIR_QUEUE_CAP);
+                    for dir in &player.next_dirs {
+                        ring_displace_back(&mut next_dirs, *dir, DIR_QUEUE_CAP);
+                    }
+                    Player {
+                        snake: Snake {
+                            items: player.cells.iter().copied().collect(),
+                        },
+                        dir: player.dir,
+                        next_dirs,
+                        score: player.score,
+                        alive: player.alive,
+                    }
+                })
+                .collect(),
+            foods: saved.foods.clone(),
+            speed_effect: saved.speed_effect,
+            seed: saved.seed,
+            rng: Rng::new(saved.rng_state),
+        })
+    }
+
+    fn in_line_with_head(&self, cell: &Cell) -> bool {
+        self.players.iter().any(|player| {
+            let head = player.snake.head();
+            match player.dir {
+                Direction::Left | Direction::Right => cell.y == head.y,
+                Direction::Up | Direction::Down => cell.x == head.x,
+            }
+        })
+    }
+
+    /// Places a food on a free cell, returning `false` when there is none left.
+    fn spawn_food(&mut self, kind: FoodKind, first: bool) -> bool {
+        let (col1, col2, row1, row2) = (0, self.config.cols - 1, 0, self.config.rows - 1);
+        let mut food = Food::new(Cell::default(), kind);
+        food.fresh = !first;
+
+        for _ in 0..RANDOM_FOOD_MAX_ATTEMPTS {
+            food.cell.x = (self.rng.next_u32() % (col2 - col1 + 1) as u32) as i32 + col1;
+            food.cell.y = (self.rng.next_u32() % (row2 - row1 + 1) as u32) as i32 + row1;
+
+            if self.is_free(&food.cell) && !(first && self.in_line_with_head(&food.cell)) {
+                self.foods.push(food);
+                return true;
+            }
+        }
+
+        // The board is nearly full, so fall back to the first free cell.
+        match self.first_free_cell() {
+            Some(cell) => {
+                food.cell = cell;
+                self.foods.push(food);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A single-player round on the default board with no food on it.
+    fn bare_sim() -> Simulation {
+        let mut sim = Simulation::new(GameConfig::default(), 7);
+        sim.foods.clear();
+        sim
+    }
+
+    fn cells(sim: &Simulation, player: usize) -> Vec<(i32, i32)> {
+        sim.player(player)
+            .snake()
+            .items
+            .iter()
+            .map(|cell| (cell.x, cell.y))
+            .collect()
+    }
+
+    #[test]
+    fn step_moves_the_snake_one_cell() {
+        let mut sim = bare_sim();
+        assert_eq!(cells(&sim, 0), [(0, 4), (1, 4), (2, 4)]);
+        assert_eq!(sim.step(), Some(vec![StepEvent::Moved(0)]));
+        assert_eq!(cells(&sim, 0), [(1, 4), (2, 4), (3, 4)]);
+        assert_eq!(sim.tick(), 1);
+    }
+
+    #[test]
+    fn snake_wraps_around_the_edges() {
+        let mut sim = bare_sim();
+        for _ in 0..14 {
+            sim.step();
+        }
+        assert_eq!(cells(&sim, 0), [(14, 4), (15, 4), (0, 4)]);
+        assert_eq!(sim.state(), State::GamePlay);
+    }
+
+    #[test]
+    fn solid_edges_kill_and_wrapping_edges_do_not() {
+        for (edges, alive) in [(Edges::Solid, false), (Edges::Wrap, true)] {
+            let config = GameConfig {
+                edges,
+                ..GameConfig::default()
+            };
+            let mut sim = Simulation::new(config, 7);
+            sim.foods.clear();
+            for _ in 0..14 {
+                sim.step();
+            }
+            assert_eq!(sim.player(0).is_alive(), alive, "{}", edges);
+        }
+    }
+
+    #[test]
+    fn snake_grows_on_food() {
+        let mut sim = bare_sim();
+        sim.foods
+            .push(Food::new(Cell { x: 3, y: 4 }, FoodKind::Normal));
+        assert_eq!(sim.step(), Some(vec![StepEvent::Ate(0, FoodKind::Normal)]));
+        assert_eq!(cells(&sim, 0), [(0, 4), (1, 4), (2, 4), (3, 4)]);
+        assert_eq!(sim.score(), 1);
+        // Another food takes the place of the eaten one.
+        assert!(sim.foods().iter().any(|food| food.kind == FoodKind::Normal));
+    }
+
+    #[test]
+    fn each_food_kind_has_its_effect() {
+        for (kind, size, score, interval) in [
+            (FoodKind::Golden, 4, 5, 0.125),
+            (FoodKind::Shrink, 2, 0, 0.125),
+            (FoodKind::SpeedUp, 4, 2, 0.0625),
+            (FoodKind::SlowDown, 4, 0, 0.25),
+        ] {
+            let mut sim = bare_sim();
+            sim.foods.push(Food::new(Cell { x: 3, y: 4 }, kind));
+            assert_eq!(sim.step(), Some(vec![StepEvent::Ate(0, kind)]));
+            assert_eq!(sim.player(0).snake().size(), size, "{:?}", kind);
+            assert_eq!(sim.score(), score, "{:?}", kind);
+            assert_eq!(sim.step_interval(), interval, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn food_disappears_when_its_time_runs_out() {
+        let mut sim = bare_sim();
+        sim.foods
+            .push(Food::new(Cell { x: 10, y: 0 }, FoodKind::Golden));
+        let lifetime = FoodKind::Golden.lifetime().unwrap();
+        for _ in 1..lifetime {
+            sim.step();
+        }
+        assert_eq!(sim.foods()[0].ttl, Some(1));
+        sim.step();
+        assert!(sim.foods().is_empty());
+    }
+
+    #[test]
+    fn snake_dies_on_itself() {
+        let mut sim = bare_sim();
+        sim.players[0].snake.items = [(2, 4), (3, 4), (4, 4), (4, 5), (3, 5)]
+            .into_iter()
+            .map(|(x, y)| Cell { x, y })
+            .collect();
+        sim.players[0].dir = Direction::Left;
+        sim.apply_input(Input::Turn(0, Direction::Up));
+        assert_eq!(
+            sim.step(),
+            Some(vec![StepEvent::Died(0, Cell { x: 3, y: 4 })])
+        );
+        assert_eq!(sim.state(), State::GameOver);
+        assert_eq!(sim.step(), None);
+    }
+
+    /// A two-player round with the snakes laid out as given, tail first.
+    fn duel(snakes: [(&[(i32, i32)], Direction); 2]) -> Simulation {
+        let config = GameConfig {
+            players: 2,
+            ..GameConfig::default()
+        };
+        let mut sim = Simulation::new(config, 7);
+        sim.foods.clear();
+        for (player, (cells, dir)) in sim.players.iter_mut().zip(snakes) {
+            player.snake.items = cells.iter().map(|&(x, y)| Cell { x, y }).collect();
+            player.dir = dir;
+        }
+        sim
+    }
+
+    #[test]
+    fn head_on_collision_kills_both() {
+        let mut sim = duel([
+            (&[(3, 4), (4, 4), (5, 4)], Direction::Right),
+            (&[(9, 4), (8, 4), (7, 4)], Direction::Left),
+        ]);
+        let head = Cell { x: 6, y: 4 };
+        assert_eq!(
+            sim.step(),
+            Some(vec![StepEvent::Died(0, head), StepEvent::Died(1, head)])
+        );
+        assert_eq!(sim.state(), State::GameOver);
+        assert_eq!(sim.winner(), None);
+    }
+
+    #[test]
+    fn hitting_another_body_kills_only_the_hitter() {
+        let mut sim = duel([
+            (&[(3, 4), (4, 4), (5, 4)], Direction::Right),
+            (&[(6, 7), (6, 6), (6, 5), (6, 4), (6, 3)], Direction::Up),
+        ]);
+        assert_eq!(
+            sim.step(),
+            Some(vec![
+                StepEvent::Died(0, Cell { x: 6, y: 4 }),
+                StepEvent::Moved(1)
+            ])
+        );
+        assert!(sim.player(1).is_alive());
+        assert_eq!(sim.state(), State::GameOver);
+        assert_eq!(sim.winner(), Some(1));
+    }
+
+    #[test]
+    fn turns_are_queued_one_per_step() {
+        let mut sim = bare_sim();
+        sim.apply_input(Input::Turn(0, Direction::Up));
+        sim.apply_input(Input::Turn(0, Direction::Left));
+        sim.step();
+        assert_eq!(sim.player(0).dir(), Direction::Up);
+        assert_eq!(*sim.player(0).snake().head(), Cell { x: 2, y: 3 });
+        sim.step();
+        assert_eq!(sim.player(0).dir(), Direction::Left);
+        assert_eq!(*sim.player(0).snake().head(), Cell { x: 1, y: 3 });
+    }
+
+    #[test]
+    fn reverse_turn_is_refused() {
+        let mut sim = bare_sim();
+        sim.apply_input(Input::Turn(0, Direction::Left));
+        sim.step();
+        assert_eq!(sim.player(0).dir(), Direction::Right);
+        assert_eq!(*sim.player(0).snake().head(), Cell { x: 3, y: 4 });
+        assert!(sim.player(0).is_alive());
+    }
+}
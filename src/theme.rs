@@ -0,0 +1,262 @@
+//! Colour themes and their text format.
+//!
+//! A theme is a list of `name colour` lines, where a colour is written like
+//! in CSS as `#rrggbb`, or `#rrggbbaa` when it is not opaque:
+//!
+//! ```text
+//! # The original look of the game.
+//! background #181818
+//! cell #183018
+//! player1.head #00ff00
+//! food.normal.body #ffa631
+//! ```
+//!
+//! Lines starting with `#` are comments. Every colour of [`Theme`] has to be
+//! given, see `themes/classic.txt` for the full list.
+
+use std::fmt;
+
+use crate::{food::FoodKind, render::Color, simulation::MAX_PLAYERS};
+
+pub const BUILTIN_THEMES: &[(&str, &str)] = &[
+    ("classic", include_str!("../themes/classic.txt")),
+    ("high-contrast", include_str!("../themes/high-contrast.txt")),
+    ("colorblind", include_str!("../themes/colorblind.txt")),
+    ("monochrome", include_str!("../themes/monochrome.txt")),
+];
+
+const COMMENT: char = '#';
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SnakeColors {
+    pub head: Color,
+    pub body: Color,
+    pub tail: Color,
+    /// Gradient along the body, from the neck to the tail.
+    pub body_start: Color,
+    pub body_end: Color,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FoodColors {
+    pub body: Color,
+    pub spine: Color,
+}
+
+/// Every colour the game draws with.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Theme {
+    pub background: Color,
+    /// Every other cell of the checkerboard, the rest is `background`.
+    pub cell: Color,
+    pub wall: Color,
+    pub wall_inner: Color,
+    pub text: Color,
+    pub spine: Color,
+    pub players: [SnakeColors; MAX_PLAYERS],
+    foods: [FoodColors; FoodKind::ALL.len()],
+    /// Name of the built-in theme this was made from, if any.
+    name: Option<&'static str>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ThemeError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+impl Theme {
+    pub fn builtin(name: &str) -> Option<Theme> {
+        let (name, text) = BUILTIN_THEMES
+            .iter()
+            .find(|(theme_name, _)| *theme_name == name)?;
+        let theme: Theme = text.parse().expect("built-in themes are valid");
+        Some(Theme {
+            name: Some(name),
+            ..theme
+        })
+    }
+
+    /// Name of the built-in theme this was made from, if any.
+    pub fn builtin_name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    pub fn food(&self, kind: FoodKind) -> FoodColors {
+        self.foods[kind as usize]
+    }
+
+    /// Names and values of all colours, in the order they are written.
+    fn colors(&self) -> Vec<(String, Color)> {
+        let mut colors = vec![
+            ("background".to_string(), self.background),
+            ("cell".to_string(), self.cell),
+            ("wall".to_string(), self.wall),
+            ("wall_inner".to_string(), self.wall_inner),
+            ("text".to_string(), self.text),
+            ("spine".to_string(), self.spine),
+        ];
+        for (i, player) in self.players.iter().enumerate() {
+            for (part, color) in [
+                ("head", player.head),
+                ("body", player.body),
+                ("tail", player.tail),
+                ("body_start", player.body_start),
+                ("body_end", player.body_end),
+            ] {
+                colors.push((format!("player{}.{}", i + 1, part), color));
+            }
+        }
+        for kind in FoodKind::ALL {
+            let food = self.food(kind);
+            colors.push((format!("food.{}.body", kind.name()), food.body));
+            colors.push((format!("food.{}.spine", kind.name()), food.spine));
+        }
+        colors
+    }
+
+    fn color_mut(&mut self, name: &str) -> Option<&mut Color> {
+        match name {
+            "background" => return Some(&mut self.background),
+            "cell" => return Some(&mut self.cell),
+            "wall" => return Some(&mut self.wall),
+            "wall_inner" => return Some(&mut self.wall_inner),
+            "text" => return Some(&mut self.text),
+            "spine" => return Some(&mut self.spine),
+            _ => {}
+        }
+
+        if let Some((player, part)) = name
+            .strip_prefix("player")
+            .and_then(|rest| rest.split_once('.'))
+        {
+            let player = player.parse::<usize>().ok()?.checked_sub(1)?;
+            let colors = self.players.get_mut(player)?;
+            return match part {
+                "head" => Some(&mut colors.head),
+                "body" => Some(&mut colors.body),
+                "tail" => Some(&mut colors.tail),
+                "body_start" => Some(&mut colors.body_start),
+                "body_end" => Some(&mut colors.body_end),
+                _ => None,
+            };
+        }
+
+        let (food, part) = name.strip_prefix("food.")?.split_once('.')?;
+        let kind = FoodKind::ALL.into_iter().find(|kind| kind.name() == food)?;
+        let colors = &mut self.foods[kind as usize];
+        match part {
+            "body" => Some(&mut colors.body),
+            "spine" => Some(&mut colors.spine),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::builtin("classic").unwrap()
+    }
+}
+
+impl std::str::FromStr for Theme {
+    type Err = ThemeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |line: usize, message: String| ThemeError { line, message };
+
+        let mut theme = Theme {
+            background: Color::default(),
+            cell: Color::default(),
+            wall: Color::default(),
+            wall_inner: Color::default(),
+            text: Color::default(),
+            spine: Color::default(),
+            players: Default::default(),
+            foods: Default::default(),
+            name: None,
+        };
+        let mut seen = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(COMMENT) {
+                continue;
+            }
+            let (name, value) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| error(i + 1, format!("expected a colour after `{}`", line)))?;
+            let color = Color::from_hex(value.trim())
+                .ok_or_else(|| error(i + 1, format!("bad colour `{}`", value.trim())))?;
+            let slot = theme
+                .color_mut(name)
+                .ok_or_else(|| error(i + 1, format!("unknown colour `{}`", name)))?;
+            if seen.contains(&name) {
+                return Err(error(i + 1, format!("`{}` is given twice", name)));
+            }
+            *slot = color;
+            seen.push(name);
+        }
+
+        let lines = s.lines().count();
+        if let Some((name, _)) = theme
+            .colors()
+            .into_iter()
+            .find(|(name, _)| !seen.contains(&name.as_str()))
+        {
+            return Err(error(lines, format!("missing `{}`", name)));
+        }
+        Ok(theme)
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, color) in self.colors() {
+            writeln!(f, "{} {}", name, color)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_themes_round_trip() {
+        for (name, _) in BUILTIN_THEMES {
+            let theme = Theme::builtin(name).unwrap();
+            assert_eq!(theme.builtin_name(), Some(*name));
+            let parsed: Theme = theme.to_string().parse().unwrap();
+            assert_eq!(parsed.builtin_name(), None);
+            assert_eq!(parsed.to_string(), theme.to_string(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let classic = BUILTIN_THEMES[0].1;
+        let text = classic.replace("cell #183018", "cell #18301");
+        assert_eq!(text.parse::<Theme>().unwrap_err().line, 3);
+        let text = classic.replace("wall_inner", "wall_outer");
+        let err = text.parse::<Theme>().unwrap_err();
+        assert_eq!(
+            (err.line, err.message.as_str()),
+            (5, "unknown colour `wall_outer`")
+        );
+        let text = classic.replace("player4.tail #a0a000\n", "");
+        let err = text.parse::<Theme>().unwrap_err();
+        assert_eq!(err.message, "missing `player4.tail`");
+        let text = format!("{}text #000000\n", classic);
+        let err = text.parse::<Theme>().unwrap_err();
+        assert_eq!(err.message, "`text` is given twice");
+    }
+}
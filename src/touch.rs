@@ -0,0 +1,156 @@
+//! Swipes and taps on a touch screen.
+
+use crate::simulation::{Direction, Vec2};
+
+// Constants
+/// In CSS pixels, which keeps gestures the same size on any screen density.
+const SWIPE_MIN_DISTANCE: f32 = 30.0;
+/// How many times longer the movement along one axis has to be than along
+/// the other to count as a swipe, so diagonal drags do not turn.
+const SWIPE_DOMINANCE: f32 = 1.5;
+const TAP_MAX_DISTANCE: f32 = 10.0;
+const TAP_MAX_DURATION: f32 = 0.3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Gesture {
+    Swipe(Direction),
+    Tap,
+}
+
+struct Touch {
+    start: Vec2<f32>,
+    start_time: f32,
+    /// Where the last swipe of this touch was recognized.
+    anchor: Vec2<f32>,
+    swiped: bool,
+}
+
+/// Turns the pointer events of one finger into gestures. Positions are in
+/// screen pixels with y going down, times in seconds.
+#[derive(Default)]
+pub struct SwipeDetector {
+    touch: Option<Touch>,
+}
+
+fn swipe_direction(delta: Vec2<f32>) -> Option<Direction> {
+    let (dx, dy) = (delta.x.abs(), delta.y.abs());
+    if dx.max(dy) < SWIPE_MIN_DISTANCE {
+        return None;
+    }
+    if dx >= dy * SWIPE_DOMINANCE {
+        Some(if delta.x > 0.0 {
+            Direction::Right
+        } else {
+            Direction::Left
+        })
+    } else if dy >= dx * SWIPE_DOMINANCE {
+        Some(if delta.y > 0.0 {
+            Direction::Down
+        } else {
+            Direction::Up
+        })
+    } else {
+        None
+    }
+}
+
+impl SwipeDetector {
+    pub const fn new() -> Self {
+        Self { touch: None }
+    }
+
+    pub fn pointer_down(&mut self, pos: Vec2<f32>, time: f32) {
+        self.touch = Some(Touch {
+            start: pos,
+            start_time: time,
+            anchor: pos,
+            swiped: false,
+        });
+    }
+
+    /// A swipe as soon as the finger has gone far enough since it touched
+    /// down or last swiped, so one drag around a corner turns twice.
+    pub fn pointer_move(&mut self, pos: Vec2<f32>) -> Option<Gesture> {
+        let touch = self.touch.as_mut()?;
+        let dir = swipe_direction(pos - touch.anchor)?;
+        touch.anchor = pos;
+        touch.swiped = true;
+        Some(Gesture::Swipe(dir))
+    }
+
+    /// A quick flick that no move event caught, or a tap.
+    pub fn pointer_up(&mut self, pos: Vec2<f32>, time: f32) -> Option<Gesture> {
+        let touch = self.touch.take()?;
+        if touch.swiped {
+            return swipe_direction(pos - touch.anchor).map(Gesture::Swipe);
+        }
+        if let Some(dir) = swipe_direction(pos - touch.start) {
+            return Some(Gesture::Swipe(dir));
+        }
+        let delta = pos - touch.start;
+        let distance = (delta.x * delta.x + delta.y * delta.y).sqrt();
+        if distance <= TAP_MAX_DISTANCE && time - touch.start_time <= TAP_MAX_DURATION {
+            Some(Gesture::Tap)
+        } else {
+            None
+        }
+    }
+
+    /// Forgets the touch, e.g. when the browser takes it over for scrolling.
+    pub fn cancel(&mut self) {
+        self.touch = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(x: f32, y: f32) -> Vec2<f32> {
+        Vec2 { x, y }
+    }
+
+    #[test]
+    fn swipes_follow_the_dominant_axis() {
+        let mut swipes = SwipeDetector::new();
+        swipes.pointer_down(pos(100.0, 100.0), 0.0);
+        assert_eq!(swipes.pointer_move(pos(120.0, 105.0)), None);
+        assert_eq!(
+            swipes.pointer_move(pos(140.0, 110.0)),
+            Some(Gesture::Swipe(Direction::Right))
+        );
+        // Diagonal, then up around the corner.
+        assert_eq!(swipes.pointer_move(pos(170.0, 80.0)), None);
+        assert_eq!(
+            swipes.pointer_move(pos(145.0, 60.0)),
+            Some(Gesture::Swipe(Direction::Up))
+        );
+        assert_eq!(swipes.pointer_up(pos(145.0, 58.0), 0.5), None);
+    }
+
+    #[test]
+    fn flick_without_moves_is_a_swipe() {
+        let mut swipes = SwipeDetector::new();
+        swipes.pointer_down(pos(100.0, 100.0), 0.0);
+        assert_eq!(
+            swipes.pointer_up(pos(95.0, 160.0), 0.1),
+            Some(Gesture::Swipe(Direction::Down))
+        );
+        assert_eq!(swipes.pointer_up(pos(95.0, 160.0), 0.1), None);
+    }
+
+    #[test]
+    fn taps_are_short_and_still() {
+        let mut swipes = SwipeDetector::new();
+        swipes.pointer_down(pos(100.0, 100.0), 0.0);
+        assert_eq!(swipes.pointer_move(pos(104.0, 97.0)), None);
+        assert_eq!(swipes.pointer_up(pos(104.0, 97.0), 0.2), Some(Gesture::Tap));
+
+        swipes.pointer_down(pos(100.0, 100.0), 0.0);
+        assert_eq!(swipes.pointer_up(pos(100.0, 100.0), 1.0), None);
+
+        swipes.pointer_down(pos(100.0, 100.0), 0.0);
+        swipes.cancel();
+        assert_eq!(swipes.pointer_up(pos(100.0, 100.0), 0.1), None);
+    }
+}
@@ -11,11 +11,27 @@ pub fn ilerpf(a: f32, b: f32, v: f32) -> f32 {
 const RAND_A: u64 = 6364136223846793005;
 const RAND_C: u64 = 1442695040888963407;
 
-pub fn rand() -> u32 {
-    static mut RAND_STATE: u64 = 0;
-    unsafe {
-        RAND_STATE = RAND_STATE.wrapping_mul(RAND_A).wrapping_add(RAND_C);
-        ((RAND_STATE >> 32) & 0xFFFFFFFF) as u32
+#[derive(Clone, Copy, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state = self.state.wrapping_mul(RAND_A).wrapping_add(RAND_C);
+        ((self.state >> 32) & 0xFFFFFFFF) as u32
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    pub fn state(&self) -> u64 {
+        self.state
     }
 }
 
@@ -29,3 +45,37 @@ pub fn ring_displace_back<T>(ring: &mut VecDeque<T>, item: T, capacity: usize) {
     }
     ring.push_back(item);
 }
+
+pub fn log(message: &str) {
+    #[cfg(target_arch = "wasm32")]
+    web_sys::console::log_1(&message.into());
+    #[cfg(not(target_arch = "wasm32"))]
+    eprintln!("{}", message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take(rng: &mut Rng, n: usize) -> Vec<u32> {
+        (0..n).map(|_| rng.next_u32()).collect()
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_sequence() {
+        assert_eq!(take(&mut Rng::new(42), 8), take(&mut Rng::new(42), 8));
+    }
+
+    #[test]
+    fn different_seeds_give_different_sequences() {
+        assert_ne!(take(&mut Rng::new(42), 8), take(&mut Rng::new(43), 8));
+    }
+
+    #[test]
+    fn state_continues_the_sequence() {
+        let mut rng = Rng::new(42);
+        take(&mut rng, 5);
+        let mut resumed = Rng::new(rng.state());
+        assert_eq!(take(&mut resumed, 8), take(&mut rng, 8));
+    }
+}
@@ -0,0 +1,202 @@
+//! Renders `Game` in a few known states and compares the frames with the
+//! images in `tests/golden/`. Run with `UPDATE_GOLDEN=1` to rewrite them after
+//! an intended change of the look. On a mismatch the actual frame and a diff
+//! are written next to the build output.
+
+use std::path::PathBuf;
+
+use snake_rust_wasm::{
+    config::{Edges, GameConfig},
+    controller::Strategy,
+    game::Game,
+    render::{Framebuffer, FramebufferRenderer},
+    simulation::State,
+};
+
+const SEED: u64 = 7;
+/// Largest difference allowed in any channel of a pixel.
+const CHANNEL_TOLERANCE: u8 = 8;
+/// Share of the pixels allowed to differ by more than the tolerance.
+const MAX_DIFF_PERCENT: f32 = 0.1;
+const DIFF_COLOR: [u8; 3] = [0xFF, 0x00, 0xFF];
+
+fn config() -> GameConfig {
+    GameConfig {
+        cols: 8,
+        rows: 5,
+        cell_size: 20,
+        ..Default::default()
+    }
+}
+
+fn new_game(config: GameConfig) -> (Game<FramebufferRenderer>, FramebufferRenderer) {
+    let renderer = FramebufferRenderer::new(config.width(), config.height());
+    (Game::new(renderer.clone(), config, SEED), renderer)
+}
+
+fn step_interval(game: &Game<FramebufferRenderer>) -> f32 {
+    game.simulation().step_interval()
+}
+
+fn variant_dir() -> PathBuf {
+    let variant = if cfg!(feature = "dev") {
+        "dev"
+    } else {
+        "plain"
+    };
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(variant)
+}
+
+fn encode_ppm(width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+    let mut out = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    out.extend_from_slice(rgb);
+    out
+}
+
+fn decode_ppm(data: &[u8]) -> (u32, u32, Vec<u8>) {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    while fields.len() < 4 {
+        let end = start
+            + data[start..]
+                .iter()
+                .position(u8::is_ascii_whitespace)
+                .expect("truncated ppm header");
+        fields.push(std::str::from_utf8(&data[start..end]).unwrap().to_string());
+        start = end + 1;
+    }
+    assert_eq!(fields[0], "P6", "not a binary ppm");
+    assert_eq!(fields[3], "255", "unsupported ppm depth");
+    let width = fields[1].parse().unwrap();
+    let height = fields[2].parse().unwrap();
+    (width, height, data[start..].to_vec())
+}
+
+fn rgb(framebuffer: &Framebuffer) -> Vec<u8> {
+    framebuffer
+        .pixels()
+        .chunks_exact(4)
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect()
+}
+
+fn assert_golden(name: &str, renderer: &FramebufferRenderer) {
+    let framebuffer = renderer.framebuffer();
+    let (width, height) = (framebuffer.width(), framebuffer.height());
+    let actual = rgb(&framebuffer);
+    let path = variant_dir().join(format!("{}.ppm", name));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(variant_dir()).unwrap();
+        std::fs::write(&path, encode_ppm(width, height, &actual)).unwrap();
+        return;
+    }
+
+    let data = std::fs::read(&path).unwrap_or_else(|err| {
+        panic!(
+            "{}: {} (run with UPDATE_GOLDEN=1 to create it)",
+            path.display(),
+            err
+        )
+    });
+    let (expected_width, expected_height, expected) = decode_ppm(&data);
+    assert_eq!(
+        (expected_width, expected_height),
+        (width, height),
+        "{}: size differs",
+        name
+    );
+
+    let mut diff = actual.clone();
+    let mut bad_pixels = 0;
+    for (i, (a, e)) in actual
+        .chunks_exact(3)
+        .zip(expected.chunks_exact(3))
+        .enumerate()
+    {
+        if a.iter()
+            .zip(e)
+            .any(|(a, e)| a.abs_diff(*e) > CHANNEL_TOLERANCE)
+        {
+            bad_pixels += 1;
+            diff[i * 3..i * 3 + 3].copy_from_slice(&DIFF_COLOR);
+        }
+    }
+
+    let allowed = (width * height) as f32 * MAX_DIFF_PERCENT / 100.0;
+    if bad_pixels as f32 > allowed {
+        let out_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let actual_path = out_dir.join(format!("{}.actual.ppm", name));
+        let diff_path = out_dir.join(format!("{}.diff.ppm", name));
+        std::fs::write(&actual_path, encode_ppm(width, height, &actual)).unwrap();
+        std::fs::write(&diff_path, encode_ppm(width, height, &diff)).unwrap();
+        panic!(
+            "{}: {} pixels differ, see {} and {}",
+            name,
+            bad_pixels,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+#[test]
+fn fresh_restart() {
+    let (mut game, renderer) = new_game(config());
+    game.restart_with_seed(SEED);
+    game.render();
+    assert_golden("fresh_restart", &renderer);
+}
+
+#[test]
+fn mid_slide() {
+    let (mut game, renderer) = new_game(config());
+    game.update(0.0);
+    game.update(step_interval(&game) * 0.5);
+    game.render();
+    assert_golden("mid_slide", &renderer);
+}
+
+#[test]
+fn eating() {
+    let (mut game, renderer) = new_game(config());
+    game.set_controller(0, Some(Strategy::Bfs.controller()));
+    while game.simulation().score() == 0 {
+        game.update(step_interval(&game));
+    }
+    game.update(step_interval(&game) * 0.25);
+    game.render();
+    assert_golden("eating", &renderer);
+}
+
+#[test]
+fn paused() {
+    let (mut game, renderer) = new_game(config());
+    game.update(0.0);
+    game.update(step_interval(&game) * 0.5);
+    game.keydown("Space", " ");
+    assert_eq!(game.simulation().state(), State::Pause);
+    game.update(1.0);
+    game.render();
+    assert_golden("paused", &renderer);
+}
+
+#[test]
+fn game_over_explosion() {
+    let (mut game, renderer) = new_game(GameConfig {
+        edges: Edges::Solid,
+        ..config()
+    });
+    game.keydown("KeyW", "w");
+    while game.simulation().state() != State::GameOver {
+        game.update(step_interval(&game));
+    }
+    for _ in 0..10 {
+        game.update(0.1);
+    }
+    game.render();
+    assert_golden("game_over_explosion", &renderer);
+}